@@ -0,0 +1,59 @@
+// Draws the visible part of the image texture over the transparency background
+
+struct VertexOutput {
+    [[location(0)]] tex_coord: vec2<f32>;
+    [[builtin(position)]] position: vec4<f32>;
+};
+
+struct Locals {
+    // Clip space rectangle the image covers, left, top, right, bottom
+    dest: vec4<f32>;
+    // Texture coordinates of the visible source region, left, top, right, bottom
+    source: vec4<f32>;
+    // Window pixel position of the image corner, then texture coordinates per window pixel
+    step: vec4<f32>;
+    // Light checker square, or the solid background colour
+    light: vec4<f32>;
+    // Dark checker square, equal to light for a solid background
+    dark: vec4<f32>;
+    // Checker square size in pixels, then samples per axis to average when scaling down
+    params: vec4<f32>;
+};
+[[group(0), binding(2)]] var<uniform> r_locals: Locals;
+
+[[stage(vertex)]]
+fn vs_main([[builtin(vertex_index)]] index: u32) -> VertexOutput {
+    // Triangle strip over the four corners of the destination rectangle
+    let corner = vec2<f32>(f32(index & 1u), f32(index >> 1u));
+
+    var out: VertexOutput;
+    out.tex_coord = mix(r_locals.source.xy, r_locals.source.zw, corner);
+    out.position = vec4<f32>(mix(r_locals.dest.xy, r_locals.dest.zw, corner), 0.0, 1.0);
+    return out;
+}
+
+[[group(0), binding(0)]] var r_tex_color: texture_2d<f32>;
+[[group(0), binding(1)]] var r_tex_sampler: sampler;
+
+[[stage(fragment)]]
+fn fs_main(input: VertexOutput) -> [[location(0)]] vec4<f32> {
+    let taps = i32(r_locals.params.y);
+
+    var color = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+    for (var y: i32 = 0; y < taps; y = y + 1) {
+        for (var x: i32 = 0; x < taps; x = x + 1) {
+            let offset = (vec2<f32>(f32(x), f32(y)) + 0.5) / f32(taps) - 0.5;
+            let coord = input.tex_coord + offset * r_locals.step.zw;
+            color = color + textureSampleLevel(r_tex_color, r_tex_sampler, coord, 0.0);
+        }
+    }
+    color = color / f32(taps * taps);
+
+    let cell = floor((input.position.xy - r_locals.step.xy) / r_locals.params.x);
+    var behind = r_locals.light.rgb;
+    if (((i32(cell.x) + i32(cell.y)) & 1) == 1) {
+        behind = r_locals.dark.rgb;
+    }
+
+    return vec4<f32>(mix(behind, color.rgb, color.a), 1.0);
+}
@@ -0,0 +1,30 @@
+// Draws the status text strip, blended over whatever is already on screen
+
+struct VertexOutput {
+    [[location(0)]] tex_coord: vec2<f32>;
+    [[builtin(position)]] position: vec4<f32>;
+};
+
+struct Locals {
+    // Clip space rectangle the strip covers, left, top, right, bottom
+    dest: vec4<f32>;
+};
+[[group(0), binding(2)]] var<uniform> r_locals: Locals;
+
+[[stage(vertex)]]
+fn vs_main([[builtin(vertex_index)]] index: u32) -> VertexOutput {
+    let corner = vec2<f32>(f32(index & 1u), f32(index >> 1u));
+
+    var out: VertexOutput;
+    out.tex_coord = corner;
+    out.position = vec4<f32>(mix(r_locals.dest.xy, r_locals.dest.zw, corner), 0.0, 1.0);
+    return out;
+}
+
+[[group(0), binding(0)]] var r_tex_color: texture_2d<f32>;
+[[group(0), binding(1)]] var r_tex_sampler: sampler;
+
+[[stage(fragment)]]
+fn fs_main(input: VertexOutput) -> [[location(0)]] vec4<f32> {
+    return textureSample(r_tex_color, r_tex_sampler, input.tex_coord);
+}
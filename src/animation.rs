@@ -0,0 +1,213 @@
+use super::errors::Result;
+use image::{
+    codecs::{ gif::GifDecoder, png::PngDecoder, webp::WebPDecoder },
+    error::{ DecodingError, ImageError, ImageFormatHint },
+    io::Reader,
+    AnimationDecoder,
+    DynamicImage,
+    Frame,
+    ImageFormat,
+};
+use std::io::{ BufRead, Seek };
+use std::time::{ Duration, Instant };
+
+/// Browsers treat tiny frame delays as unset, so do the same
+const MIN_DELAY: Duration = Duration::from_millis(10);
+const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+const MIN_SPEED: f32 = 0.125;
+const MAX_SPEED: f32 = 8.0;
+/// Longest side of the blank image shown while loading, it only needs the right aspect ratio
+const PLACEHOLDER_SIZE: f32 = 64.0;
+
+/// Decoded frames of a file and its playback state, a still image is a single frame
+#[derive(Debug)]
+pub struct Animation {
+    frames: Vec<(DynamicImage, Duration)>,
+    index: usize,
+    paused: bool,
+    speed: f32,
+    next_frame: Instant,
+    /// Format the file was decoded as, unknown for placeholders
+    format: Option<ImageFormat>,
+    /// Size of the encoded file in bytes
+    file_size: Option<u64>,
+}
+
+impl Animation {
+    /// Decodes every frame for animated GIF, APNG and WebP, otherwise the single image
+    pub fn decode<R: BufRead + Seek>(reader: Reader<R>) -> Result<Animation> {
+        let format = reader.format();
+        let mut animation = Animation::decode_frames(reader)?;
+        animation.format = format;
+        Ok(animation)
+    }
+
+    fn decode_frames<R: BufRead + Seek>(reader: Reader<R>) -> Result<Animation> {
+        let format = reader.format();
+        let frames: Vec<Frame> = match format {
+            Some(ImageFormat::Gif) => GifDecoder::new(reader.into_inner())?
+                .into_frames()
+                .collect_frames()?,
+            Some(ImageFormat::Png) => {
+                let decoder = PngDecoder::new(reader.into_inner())?;
+                if !decoder.is_apng() {
+                    return Ok(Animation::still(DynamicImage::from_decoder(decoder)?));
+                }
+                decoder.apng().into_frames().collect_frames()?
+            }
+            Some(ImageFormat::WebP) => {
+                let mut reader = reader.into_inner();
+                let frames = WebPDecoder::new(&mut reader)?.into_frames().collect_frames()?;
+                if frames.is_empty() {
+                    // Simple lossy and lossless files yield no frames, decode them again as a still
+                    reader.rewind()?;
+                    let reader = Reader::with_format(reader, ImageFormat::WebP);
+                    return Ok(Animation::still(reader.decode()?));
+                }
+                frames
+            }
+            _ => return Ok(Animation::still(reader.decode()?)),
+        };
+
+        if frames.is_empty() {
+            let format = format.map_or(ImageFormatHint::Unknown, ImageFormatHint::Exact);
+            return Err(ImageError::Decoding(DecodingError::new(format, "no frames")).into());
+        }
+        if frames.len() == 1 {
+            let frame = frames.into_iter().next().unwrap();
+            return Ok(Animation::still(DynamicImage::ImageRgba8(frame.into_buffer())));
+        }
+
+        let frames: Vec<(DynamicImage, Duration)> = frames
+            .into_iter()
+            .map(|frame| {
+                let delay = Duration::from(frame.delay());
+                let delay = if delay <= MIN_DELAY { DEFAULT_DELAY } else { delay };
+                (DynamicImage::ImageRgba8(frame.into_buffer()), delay)
+            })
+            .collect();
+
+        Ok(Animation {
+            next_frame: Instant::now() + frames[0].1,
+            frames,
+            index: 0,
+            paused: false,
+            speed: 1.0,
+            format: None,
+            file_size: None,
+        })
+    }
+
+    pub fn still(image: DynamicImage) -> Animation {
+        Animation {
+            frames: vec![(image, Duration::ZERO)],
+            index: 0,
+            paused: true,
+            speed: 1.0,
+            next_frame: Instant::now(),
+            format: None,
+            file_size: None,
+        }
+    }
+
+    /// Blank stand in with the aspect ratio of an image that is still loading
+    pub fn placeholder(image_size: [u32; 2]) -> Animation {
+        let scale = (PLACEHOLDER_SIZE / (image_size[0].max(image_size[1]) as f32)).min(1.0);
+        let side = |length: u32| ((length as f32) * scale).round().max(1.0) as u32;
+        Animation::still(DynamicImage::new_rgba8(side(image_size[0]), side(image_size[1])))
+    }
+
+    /// Frame currently on display
+    pub fn image(&self) -> &DynamicImage {
+        &self.frames[self.index].0
+    }
+
+    /// Replaces every frame with the result of `op`, e.g. to rotate the whole animation
+    pub fn map_frames(&mut self, op: impl Fn(&DynamicImage) -> DynamicImage) {
+        for (frame, _) in self.frames.iter_mut() {
+            *frame = op(frame);
+        }
+    }
+
+    pub fn format(&self) -> Option<ImageFormat> {
+        self.format
+    }
+
+    pub fn file_size(&self) -> Option<u64> {
+        self.file_size
+    }
+
+    pub fn set_file_size(&mut self, file_size: u64) {
+        self.file_size = Some(file_size);
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.frames.len() > 1
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// When the next frame is due, if playing
+    pub fn deadline(&self) -> Option<Instant> {
+        if self.is_animated() && !self.paused {
+            Some(self.next_frame)
+        } else {
+            None
+        }
+    }
+
+    /// Advances to the next frame if it is due, returning whether the frame changed
+    pub fn tick(&mut self, now: Instant) -> bool {
+        match self.deadline() {
+            Some(deadline) if now >= deadline => {
+                self.index = (self.index + 1) % self.frames.len();
+                self.next_frame += self.delay();
+                // Don't try to catch up after falling far behind, e.g. after a suspend
+                if self.next_frame < now {
+                    self.next_frame = now + self.delay();
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        if !self.is_animated() {
+            return;
+        }
+        self.paused = !self.paused;
+        if !self.paused {
+            self.next_frame = Instant::now() + self.delay();
+        }
+    }
+
+    /// Pauses playback and moves a single frame forwards or backwards
+    pub fn step(&mut self, forward: bool) {
+        if !self.is_animated() {
+            return;
+        }
+        self.paused = true;
+        let len = self.frames.len();
+        self.index = if forward { (self.index + 1) % len } else { (self.index + len - 1) % len };
+    }
+
+    /// Multiplies the playback speed, clamped to a sensible range
+    pub fn change_speed(&mut self, factor: f32) {
+        self.speed = (self.speed * factor).clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    pub fn reset_speed(&mut self) {
+        self.speed = 1.0;
+    }
+
+    fn delay(&self) -> Duration {
+        self.frames[self.index].1.div_f32(self.speed)
+    }
+}
@@ -1,16 +1,306 @@
-use clap::Parser;
+use super::errors::{ RviError, Result };
+use super::graphics::{ Background, Filter };
+use super::keymap::Keymap;
+use super::overlay::DEFAULT_FORMAT;
+use super::renderer::Backend;
+use super::slideshow::parse_interval;
+use super::transform::parse_rotation;
+use super::view::Fit;
+use super::window::{ parse_screen_size, Placement };
+use clap::{ parser::ValueSource, ArgMatches, CommandFactory, FromArgMatches, Parser, ValueEnum };
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{ Path, PathBuf };
+use std::time::Duration;
+use winit::dpi::PhysicalSize;
+
+/// Name of the directory holding riv's files inside the platform config directory
+const CONFIG_DIR: &str = "riv";
+const CONFIG_FILE: &str = "config.toml";
 
 #[derive(Debug, Parser)]
 #[clap(author, version, about)]
 pub struct Config {
-    /// Name of image to open
-    pub file_name: String,
+    /// Images or directories of images to open, `-` or a pipe reads from standard input
+    pub file_names: Vec<String>,
 
     /// Wether to scale the image up
-    #[clap(short, long, takes_value = false)]
+    #[clap(short, long, takes_value = false, overrides_with = "no-up-scale")]
     pub up_scale: bool,
 
+    /// Don't scale the image up, even if the config file does
+    #[clap(long, takes_value = false, overrides_with = "up-scale")]
+    pub no_up_scale: bool,
+
     /// Whether to force integrated gpu
-    #[clap(short, long, takes_value = false)]
+    #[clap(short, long, takes_value = false, overrides_with = "no-low-performance-mode")]
     pub low_performance_mode: bool,
+
+    /// Don't force the integrated gpu, even if the config file does
+    #[clap(long, takes_value = false, overrides_with = "low-performance-mode")]
+    pub no_low_performance_mode: bool,
+
+    /// How to draw the window: the GPU with a software fallback, only the GPU, or only
+    /// software, which needs an X11 display
+    #[clap(long, value_enum, default_value_t = Backend::Auto)]
+    pub renderer: Backend,
+
+    /// Degrees to rotate images clockwise, in multiples of 90
+    #[clap(long, default_value_t = 0, allow_hyphen_values = true, value_parser = parse_rotation)]
+    pub rotate: u32,
+
+    /// Don't rotate images upright according to their EXIF orientation
+    #[clap(long, takes_value = false, overrides_with = "auto-orient")]
+    pub no_auto_orient: bool,
+
+    /// Rotate images upright by their EXIF orientation, even if the config file doesn't
+    #[clap(long, takes_value = false, overrides_with = "no-auto-orient")]
+    pub auto_orient: bool,
+
+    /// Background behind transparent images: checkerboard, a colour name or #rrggbb
+    #[clap(short, long, default_value = "checkerboard")]
+    pub background: Background,
+
+    /// Resampling filter used when scaling images
+    #[clap(short, long, value_enum, default_value_t = Filter::Auto)]
+    pub filter: Filter,
+
+    /// How images are scaled to the window
+    #[clap(long, value_enum, default_value_t = Fit::Contain)]
+    pub fit: Fit,
+
+    /// Reload the image whenever it changes on disk
+    #[clap(short, long, takes_value = false, overrides_with = "no-watch")]
+    pub watch: bool,
+
+    /// Don't reload the image when it changes, even if the config file does
+    #[clap(long, takes_value = false, overrides_with = "watch")]
+    pub no_watch: bool,
+
+    /// Start in borderless fullscreen on the current monitor
+    #[clap(long, takes_value = false, overrides_with = "no-fullscreen")]
+    pub fullscreen: bool,
+
+    /// Start in a window, even if the config file starts fullscreen
+    #[clap(long, takes_value = false, overrides_with = "fullscreen")]
+    pub no_fullscreen: bool,
+
+    /// Open the window without a title bar or border
+    #[clap(long, takes_value = false, overrides_with = "no-borderless")]
+    pub borderless: bool,
+
+    /// Keep the title bar and border, even if the config file removes them
+    #[clap(long, takes_value = false, overrides_with = "borderless")]
+    pub no_borderless: bool,
+
+    /// Keep the window above other windows
+    #[clap(long, takes_value = false, overrides_with = "no-always-on-top")]
+    pub always_on_top: bool,
+
+    /// Don't keep the window on top, even if the config file does
+    #[clap(long, takes_value = false, overrides_with = "always-on-top")]
+    pub no_always_on_top: bool,
+
+    /// Advance to the next image every this many seconds
+    #[clap(long, value_name = "SECONDS", value_parser = parse_interval)]
+    pub slideshow: Option<Duration>,
+
+    /// Start over from the first image once the slideshow reaches the last
+    #[clap(long = "loop", takes_value = false, overrides_with = "no-loop")]
+    pub loop_slideshow: bool,
+
+    /// Stop the slideshow at the last image, even if the config file loops
+    #[clap(long, takes_value = false, overrides_with = "loop-slideshow")]
+    pub no_loop: bool,
+
+    /// Open the images in a random order
+    #[clap(long, takes_value = false, overrides_with = "no-shuffle")]
+    pub shuffle: bool,
+
+    /// Keep the images in order, even if the config file shuffles them
+    #[clap(long, takes_value = false, overrides_with = "shuffle")]
+    pub no_shuffle: bool,
+
+    /// Render the first image to this file, as it would first appear on a --screen-size
+    /// screen (640x480 unless given), instead of opening a window
+    #[clap(short, long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+
+    /// Start with the status overlay shown
+    #[clap(long, takes_value = false, overrides_with = "no-overlay")]
+    pub overlay: bool,
+
+    /// Start with the overlay hidden, even if the config file shows it
+    #[clap(long, takes_value = false, overrides_with = "overlay")]
+    pub no_overlay: bool,
+
+    /// Text of the status overlay, with {name}, {path}, {index}, {count}, {width}, {height},
+    /// {zoom}, {format} and {size} replaced
+    #[clap(long, value_name = "FORMAT", default_value = DEFAULT_FORMAT)]
+    pub overlay_format: String,
+
+    /// Largest share of the screen the window grows to, in percent
+    #[clap(long, default_value_t = 90, value_parser = clap::value_parser!(u32).range(1..=100))]
+    pub screen_percent: u32,
+
+    /// Size of screen to fit the window to, as WIDTHxHEIGHT in pixels, instead of asking the
+    /// monitor
+    #[clap(long, value_name = "WxH", env = "RIV_SCREEN_SIZE", value_parser = parse_screen_size)]
+    pub screen_size: Option<PhysicalSize<u32>>,
+
+    /// Monitor to open on, by its index from 0 or its name, instead of the primary monitor
+    #[clap(long, value_name = "INDEX|NAME")]
+    pub monitor: Option<String>,
+
+    /// Where to place the window on its monitor: center, or X,Y in pixels from the top left
+    #[clap(long, value_name = "center|X,Y", default_value = "20,20", allow_hyphen_values = true)]
+    pub position: Placement,
+
+    /// Shrink the window to the image's aspect ratio once a resize by hand settles
+    #[clap(long, takes_value = false, overrides_with = "no-snap-aspect")]
+    pub snap_aspect: bool,
+
+    /// Leave the window as resized, even if the config file snaps it
+    #[clap(long, takes_value = false, overrides_with = "snap-aspect")]
+    pub no_snap_aspect: bool,
+
+    /// Read defaults from this file instead of `riv/config.toml` in the config directory
+    #[clap(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Ignore the config file and only use command line options
+    #[clap(long, takes_value = false, conflicts_with = "config")]
+    pub no_config: bool,
+
+    /// Print the key bindings in effect, in config file format, and exit
+    #[clap(long, takes_value = false)]
+    pub print_keymap: bool,
+
+    /// Key bindings, the defaults changed by the config file's `[keys]` table
+    #[clap(skip)]
+    pub keymap: Keymap,
+}
+
+/// Defaults read from the config file, keyed like the long command line options
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct ConfigFile {
+    up_scale: Option<bool>,
+    low_performance_mode: Option<bool>,
+    renderer: Option<String>,
+    rotate: Option<i32>,
+    no_auto_orient: Option<bool>,
+    background: Option<String>,
+    filter: Option<String>,
+    fit: Option<String>,
+    watch: Option<bool>,
+    fullscreen: Option<bool>,
+    borderless: Option<bool>,
+    always_on_top: Option<bool>,
+    slideshow: Option<f64>,
+    #[serde(rename = "loop")]
+    loop_slideshow: Option<bool>,
+    shuffle: Option<bool>,
+    overlay: Option<bool>,
+    overlay_format: Option<String>,
+    screen_percent: Option<u32>,
+    screen_size: Option<String>,
+    monitor: Option<String>,
+    position: Option<String>,
+    snap_aspect: Option<bool>,
+    /// Maps a key binding like `ctrl+r` to an action name, or `none` to unbind it
+    keys: HashMap<String, String>,
+}
+
+impl Config {
+    /// Parses the command line and fills in anything it left out from the config file
+    pub fn load() -> Result<Config> {
+        let matches: ArgMatches = Config::command().get_matches();
+        let mut config = Config::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+        if config.no_config {
+            return Ok(config);
+        }
+
+        // A missing file is only a problem when it was asked for by name
+        let (path, required) = match config.config.clone() {
+            Some(path) => (path, true),
+            None => match dirs::config_dir() {
+                Some(dir) => (dir.join(CONFIG_DIR).join(CONFIG_FILE), false),
+                None => return Ok(config),
+            },
+        };
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if !required && err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(config);
+            }
+            Err(err) => return Err(config_error(&path, err)),
+        };
+        let file: ConfigFile = toml::from_str(&contents).map_err(|err| config_error(&path, err))?;
+
+        config.merge(file, &matches).map_err(|err| config_error(&path, err))?;
+        Ok(config)
+    }
+
+    /// Takes each value from the file unless it was given on the command line or environment
+    fn merge(&mut self, file: ConfigFile, matches: &ArgMatches) -> std::result::Result<(), String> {
+        // Derived argument ids are the kebab case field names, like the file's keys
+        let unset = |field: &str| {
+            !matches!(
+                matches.value_source(field.replace('_', "-")),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            )
+        };
+
+        macro_rules! merge {
+            ($field:ident) => {
+                merge!($field, |value| Ok::<_, String>(value))
+            };
+            // A flag also stays as it is when its `--no-` form was given
+            ($field:ident, unless $negation:ident) => {
+                if unset(stringify!($negation)) {
+                    merge!($field);
+                }
+            };
+            ($field:ident, $parse:expr) => {
+                if let Some(value) = file.$field {
+                    if unset(stringify!($field)) {
+                        self.$field = $parse(value)?;
+                    }
+                }
+            };
+        }
+
+        merge!(up_scale, unless no_up_scale);
+        merge!(low_performance_mode, unless no_low_performance_mode);
+        merge!(renderer, |value: String| Backend::from_str(&value, true));
+        merge!(rotate, |value: i32| parse_rotation(&value.to_string()));
+        merge!(no_auto_orient, unless auto_orient);
+        merge!(background, |value: String| value.parse());
+        merge!(filter, |value: String| Filter::from_str(&value, true));
+        merge!(fit, |value: String| Fit::from_str(&value, true));
+        merge!(watch, unless no_watch);
+        merge!(fullscreen, unless no_fullscreen);
+        merge!(borderless, unless no_borderless);
+        merge!(always_on_top, unless no_always_on_top);
+        merge!(slideshow, |value: f64| parse_interval(&value.to_string()).map(Some));
+        merge!(loop_slideshow, unless no_loop);
+        merge!(shuffle, unless no_shuffle);
+        merge!(overlay, unless no_overlay);
+        merge!(overlay_format);
+        merge!(screen_percent, |value: u32| match value {
+            1..=100 => Ok(value),
+            _ => Err(format!("screen-percent `{}` is not between 1 and 100", value)),
+        });
+        merge!(screen_size, |value: String| parse_screen_size(&value).map(Some));
+        merge!(monitor, |value: String| Ok::<_, String>(Some(value)));
+        merge!(position, |value: String| value.parse());
+        merge!(snap_aspect, unless no_snap_aspect);
+
+        self.keymap.apply(file.keys)
+    }
+}
+
+fn config_error(path: &Path, err: impl ToString) -> RviError {
+    RviError::ConfigError { path: path.to_path_buf(), message: err.to_string() }
 }
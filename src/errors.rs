@@ -6,12 +6,48 @@ pub enum RviError {
     WindowError(#[from] winit::error::OsError),
     #[error("An error occurred while processing the image")]
     ImageError(#[from] image::ImageError),
-    #[error("And error occurred while loading the image")]
+    #[error("An error occurred while loading the image")]
     IoError(#[from] std::io::Error),
     #[error("Unable to create new pixels instance")]
     PixelsError(#[from] pixels::Error),
-    #[error("Cannot find primary monitor")]
+    #[error("Unable to render in software: {0}")]
+    SoftwareError(String),
+    #[error("Invalid config file {}: {message}", path.display())]
+    ConfigError { path: std::path::PathBuf, message: String },
+    #[error("Unable to watch the image for changes")]
+    WatchError(#[from] notify::Error),
+    #[error("Cannot find a monitor to fit the window to")]
     NoPrimaryMonitor,
+    #[error("No monitor matches `{monitor}`, the monitors are {}", available.join(", "))]
+    NoSuchMonitor { monitor: String, available: Vec<String> },
+    #[error("No images found to open")]
+    NoImages,
+}
+
+impl RviError {
+    /// Process exit status for the error, distinct per variant and clear of clap's usage code 2
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RviError::WindowError(_) => 3,
+            RviError::ImageError(_) => 4,
+            RviError::IoError(_) => 5,
+            RviError::PixelsError(_) => 6,
+            RviError::ConfigError { .. } => 7,
+            RviError::WatchError(_) => 8,
+            RviError::NoPrimaryMonitor => 9,
+            RviError::NoImages => 10,
+            RviError::SoftwareError(_) => 11,
+            RviError::NoSuchMonitor { .. } => 12,
+        }
+    }
+
+    /// The message followed by the underlying cause, if there is one
+    pub fn describe(&self) -> String {
+        match std::error::Error::source(self) {
+            Some(source) => format!("{}: {}", self, source),
+            None => self.to_string(),
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, RviError>;
@@ -1,6 +1,17 @@
+use super::animation::Animation;
+use super::errors::Result;
+use std::path::PathBuf;
 use winit::event_loop::EventLoop;
 
+/// Events sent to the event loop from background work
+#[derive(Debug)]
+pub enum RivEvent {
+    /// An image finished decoding, `generation` identifies the request it answers
+    Loaded { generation: u64, result: Result<Animation> },
+    /// Something in the open image's directory was written, with the paths affected
+    FileChanged(Vec<PathBuf>),
+}
 
-pub fn create_event_loop() -> EventLoop<()> {
+pub fn create_event_loop() -> EventLoop<RivEvent> {
     winit::event_loop::EventLoopBuilder::with_user_event().build()
 }
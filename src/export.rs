@@ -0,0 +1,52 @@
+use super::config::Config;
+use super::errors::Result;
+use super::graphics::{ render_image, Frame };
+use super::loader::load_now;
+use super::transform::Transform;
+use super::view::{ fit_window_size, View };
+use super::window::DEFAULT_SCREEN_SIZE;
+use image::{ imageops, RgbImage };
+use std::path::Path;
+use winit::dpi::PhysicalSize;
+
+/// Renders `input` as the viewer first shows it and saves it to `output`
+///
+/// Nothing here touches a window or the GPU, so it works on machines without a display. With no
+/// monitor to ask, the image fits `--screen-size` or else `DEFAULT_SCREEN_SIZE`. The output
+/// format follows the extension of `output`, and animations export their first frame.
+pub fn export(config: &Config, input: &Path, output: &Path) -> Result<()> {
+    let mut animation = load_now(input, !config.no_auto_orient)?;
+    let transform: Transform = Transform::from_degrees(config.rotate);
+    if !transform.is_identity() {
+        animation.map_frames(|image| transform.apply(image));
+    }
+
+    let image = animation.image();
+    let image_size: [u32; 2] = [image.width(), image.height()];
+    let view = View::new(config.fit);
+    let size: PhysicalSize<u32> = fit_window_size(
+        &config.screen_size.unwrap_or(DEFAULT_SCREEN_SIZE),
+        image_size,
+        config.screen_percent,
+        config.up_scale
+    );
+    let rendered: RgbImage = render_image(&size, &Frame {
+        image,
+        view: &view,
+        background: &config.background,
+        filter: config.filter,
+    });
+
+    // The window rounds its size up, so trim the sliver of letterbox that can leave
+    let output_size: PhysicalSize<u32> = view.output_size(image_size, &size);
+    let rendered: RgbImage = imageops::crop_imm(
+        &rendered,
+        (size.width - output_size.width) / 2,
+        (size.height - output_size.height) / 2,
+        output_size.width,
+        output_size.height
+    ).to_image();
+
+    rendered.save(output)?;
+    Ok(())
+}
@@ -0,0 +1,350 @@
+use super::errors::Result;
+use super::graphics::{ Filter, Frame, CHECKER_SIZE };
+use super::overlay::Overlay;
+use image::{ imageops::FilterType, DynamicImage };
+use pixels::{ wgpu, Pixels };
+use std::num::NonZeroU32;
+use winit::dpi::PhysicalSize;
+
+/// Most source texels averaged per axis for each window pixel when scaling down
+const MAX_TAPS: u32 = 8;
+/// Number of vec4 fields in the shader's `Locals` struct
+const LOCALS_LEN: usize = 6;
+
+/// Keeps the full resolution image on the GPU and scales it in a render pass
+///
+/// Resizing or zooming then only rewrites a small uniform buffer, instead of resampling the
+/// whole image on the CPU and uploading the result.
+#[derive(Debug)]
+pub struct ImageRenderer {
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    linear_sampler: wgpu::Sampler,
+    nearest_sampler: wgpu::Sampler,
+    /// Matches the surface so colours are only converted to and from sRGB when it does
+    texture_format: wgpu::TextureFormat,
+    image: Option<ImageTexture>,
+}
+
+#[derive(Debug)]
+struct ImageTexture {
+    texture: wgpu::Texture,
+    size: wgpu::Extent3d,
+    linear_bind_group: wgpu::BindGroup,
+    nearest_bind_group: wgpu::BindGroup,
+}
+
+impl ImageRenderer {
+    pub fn new(pixels: &Pixels) -> ImageRenderer {
+        let device: &wgpu::Device = pixels.device();
+        let texture_format = if pixels.render_texture_format().describe().srgb {
+            wgpu::TextureFormat::Rgba8UnormSrgb
+        } else {
+            wgpu::TextureFormat::Rgba8Unorm
+        };
+        let shader = wgpu::include_wgsl!("../shaders/image.wgsl");
+        let module = device.create_shader_module(&shader);
+
+        let sampler = |filter: wgpu::FilterMode| {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("riv_image_sampler"),
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter: filter,
+                min_filter: filter,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                ..Default::default()
+            })
+        };
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("riv_image_uniform_buffer"),
+            size: (LOCALS_LEN * 4 * std::mem::size_of::<f32>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("riv_image_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("riv_image_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("riv_image_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[wgpu::ColorTargetState {
+                    format: pixels.render_texture_format(),
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                }],
+            }),
+            multiview: None,
+        });
+
+        ImageRenderer {
+            bind_group_layout,
+            render_pipeline,
+            uniform_buffer,
+            linear_sampler: sampler(wgpu::FilterMode::Linear),
+            nearest_sampler: sampler(wgpu::FilterMode::Nearest),
+            texture_format,
+            image: None,
+        }
+    }
+
+    /// Whether an image is uploaded and can be drawn by `render`
+    pub fn is_ready(&self) -> bool {
+        self.image.is_some()
+    }
+
+    /// Whether `render` can draw with `filter`, the kernel filters are left to the CPU
+    pub fn can_draw(&self, filter: Filter) -> bool {
+        self.is_ready() && filter.on_gpu()
+    }
+
+    /// Uploads the image, reusing the texture when the size is unchanged
+    ///
+    /// Images larger than the device allows are dropped, leaving the renderer not ready so the
+    /// caller falls back to scaling on the CPU.
+    pub fn upload(&mut self, pixels: &Pixels, image: &DynamicImage) {
+        let device: &wgpu::Device = pixels.device();
+        let max_size = device.limits().max_texture_dimension_2d;
+        if image.width() > max_size || image.height() > max_size {
+            if cfg!(debug_assertions) {
+                println!("Image larger than {} pixels, scaling on the CPU", max_size);
+            }
+            self.image = None;
+            return;
+        }
+
+        let size = wgpu::Extent3d {
+            width: image.width(),
+            height: image.height(),
+            depth_or_array_layers: 1,
+        };
+
+        if self.image.as_ref().map(|texture| texture.size) != Some(size) {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("riv_image_texture"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: self.texture_format,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let bind_group = |sampler: &wgpu::Sampler| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("riv_image_bind_group"),
+                    layout: &self.bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(sampler),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: self.uniform_buffer.as_entire_binding(),
+                        },
+                    ],
+                })
+            };
+
+            self.image = Some(ImageTexture {
+                linear_bind_group: bind_group(&self.linear_sampler),
+                nearest_bind_group: bind_group(&self.nearest_sampler),
+                texture,
+                size,
+            });
+        }
+
+        if let Some(texture) = &self.image {
+            pixels.queue().write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture.texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                image.to_rgba8().as_raw(),
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(4 * size.width),
+                    rows_per_image: NonZeroU32::new(size.height),
+                },
+                size
+            );
+        }
+    }
+
+    /// Draws the last uploaded image as `frame` describes, leaving the pixel buffer alone
+    pub fn render(
+        &self,
+        pixels: &Pixels,
+        size: &PhysicalSize<u32>,
+        frame: &Frame,
+        overlay: Option<&Overlay>,
+    ) -> Result<()> {
+        let Frame { view, background, filter, .. } = *frame;
+        let texture = match &self.image {
+            Some(texture) => texture,
+            None => return Ok(()),
+        };
+
+        let image_size: [u32; 2] = [texture.size.width, texture.size.height];
+        let [x, y, width, height] = view.source_rect(image_size, size);
+        let output: PhysicalSize<u32> = view.output_size(image_size, size);
+        let scale: f32 = view.scale(image_size, size);
+
+        // Centre the image in the window, like the pixel buffer is on the CPU path
+        let window = [size.width as f32, size.height as f32];
+        let left = ((size.width - output.width) / 2) as f32;
+        let top = ((size.height - output.height) / 2) as f32;
+        let right = left + (output.width as f32);
+        let bottom = top + (output.height as f32);
+
+        let source = [
+            (x as f32) / (image_size[0] as f32),
+            (y as f32) / (image_size[1] as f32),
+            ((x + width) as f32) / (image_size[0] as f32),
+            ((y + height) as f32) / (image_size[1] as f32),
+        ];
+
+        let nearest = filter.filter_type(scale) == FilterType::Nearest;
+        let taps = if nearest { 1 } else { ((0.5 / scale).ceil() as u32).clamp(1, MAX_TAPS) };
+
+        let srgb = self.texture_format.describe().srgb;
+        let [light, dark] = background.colors();
+        let [red, green, blue, _] = shader_color(background.letterbox(), srgb);
+        let letterbox = wgpu::Color {
+            r: red as f64,
+            g: green as f64,
+            b: blue as f64,
+            a: 1.0,
+        };
+        let locals: [[f32; 4]; LOCALS_LEN] = [
+            [
+                left / window[0] * 2.0 - 1.0,
+                1.0 - top / window[1] * 2.0,
+                right / window[0] * 2.0 - 1.0,
+                1.0 - bottom / window[1] * 2.0,
+            ],
+            source,
+            [
+                left,
+                top,
+                (source[2] - source[0]) / (output.width as f32),
+                (source[3] - source[1]) / (output.height as f32),
+            ],
+            shader_color(light, srgb),
+            shader_color(dark, srgb),
+            [CHECKER_SIZE as f32, taps as f32, 0.0, 0.0],
+        ];
+        let bytes: Vec<u8> = locals
+            .iter()
+            .flatten()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+        pixels.queue().write_buffer(&self.uniform_buffer, 0, &bytes);
+
+        let bind_group = if nearest {
+            &texture.nearest_bind_group
+        } else {
+            &texture.linear_bind_group
+        };
+
+        pixels.render_with(|encoder, render_target, _context| {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("riv_image_render_pass"),
+                color_attachments: &[wgpu::RenderPassColorAttachment {
+                    view: render_target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(letterbox),
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+            rpass.set_pipeline(&self.render_pipeline);
+            rpass.set_bind_group(0, bind_group, &[]);
+            rpass.draw(0..4, 0..1);
+            drop(rpass);
+
+            if let Some(overlay) = overlay {
+                overlay.draw(encoder, render_target);
+            }
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+}
+
+/// An sRGB surface encodes what the shader outputs, so colours need to be linear going in
+fn shader_color(color: [u8; 3], srgb: bool) -> [f32; 4] {
+    let channel = |value: u8| {
+        let value = (value as f32) / 255.0;
+        if !srgb {
+            value
+        } else if value <= 0.04045 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    [channel(color[0]), channel(color[1]), channel(color[2]), 1.0]
+}
@@ -1,51 +1,347 @@
-use super::errors::Result;
-use image::{DynamicImage, FlatSamples, imageops::FilterType};
+use super::errors::{ Result, RviError };
+use super::gpu::ImageRenderer;
+use super::overlay::Overlay;
+use super::view::View;
+use image::{DynamicImage, FlatSamples, RgbImage, RgbaImage, imageops::FilterType};
+use clap::ValueEnum;
 use pixels::Pixels;
+use pixels::wgpu::SurfaceError;
+use std::str::FromStr;
 use winit::dpi::PhysicalSize;
 
+/// Side length in pixels of the squares drawn behind transparent images
+pub const CHECKER_SIZE: u32 = 8;
+const CHECKER_LIGHT: [u8; 3] = [0xff, 0xff, 0xff];
+const CHECKER_DARK: [u8; 3] = [0xcc, 0xcc, 0xcc];
+
+/// What transparent parts of an image are composited over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Background {
+    Checkerboard,
+    Solid([u8; 3]),
+}
+
+impl Background {
+    /// Backgrounds to step through at runtime, starting from the configured one
+    pub fn cycle(configured: Background) -> Vec<Background> {
+        let mut backgrounds = vec![configured];
+        for background in [
+            Background::Checkerboard,
+            Background::Solid([0x00, 0x00, 0x00]),
+            Background::Solid([0xff, 0xff, 0xff]),
+        ] {
+            if !backgrounds.contains(&background) {
+                backgrounds.push(background);
+            }
+        }
+        backgrounds
+    }
+
+    /// Colours of the light and dark checker squares, both the same for a solid colour
+    pub fn colors(&self) -> [[u8; 3]; 2] {
+        match self {
+            Background::Checkerboard => [CHECKER_LIGHT, CHECKER_DARK],
+            Background::Solid(color) => [*color, *color],
+        }
+    }
+
+    /// Colour around an image that doesn't fill the window, black behind a checkerboard
+    pub fn letterbox(&self) -> [u8; 3] {
+        match self {
+            Background::Checkerboard => [0x00, 0x00, 0x00],
+            Background::Solid(color) => *color,
+        }
+    }
+
+    fn color_at(&self, x: u32, y: u32) -> [u8; 3] {
+        self.colors()[(((x / CHECKER_SIZE) ^ (y / CHECKER_SIZE)) & 1) as usize]
+    }
+}
+
+/// Resampling filter used when scaling the image for display
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Filter {
+    /// Smooth when scaling down, nearest for whole multiples of scaling up
+    Auto,
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Gaussian,
+    Lanczos3,
+}
+
+impl Filter {
+    /// Filter to switch to when cycling at runtime
+    pub fn next(&self) -> Filter {
+        match self {
+            Filter::Auto => Filter::Nearest,
+            Filter::Nearest => Filter::Triangle,
+            Filter::Triangle => Filter::CatmullRom,
+            Filter::CatmullRom => Filter::Gaussian,
+            Filter::Gaussian => Filter::Lanczos3,
+            Filter::Lanczos3 => Filter::Auto,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Filter::Auto => "auto",
+            Filter::Nearest => "nearest",
+            Filter::Triangle => "triangle",
+            Filter::CatmullRom => "catmull-rom",
+            Filter::Gaussian => "gaussian",
+            Filter::Lanczos3 => "lanczos3",
+        }
+    }
+
+    /// Whether the GPU shader reproduces the filter, which it does for all but the kernels
+    /// that need more than a box of bilinear taps
+    pub fn on_gpu(&self) -> bool {
+        matches!(self, Filter::Auto | Filter::Nearest | Filter::Triangle)
+    }
+
+    /// Picks the concrete filter to scale by `scale` displayed pixels per source pixel
+    pub fn filter_type(&self, scale: f32) -> FilterType {
+        match self {
+            Filter::Auto if scale >= 1.0 && (scale - scale.round()).abs() < 0.001 => {
+                FilterType::Nearest
+            }
+            Filter::Auto => FilterType::Triangle,
+            Filter::Nearest => FilterType::Nearest,
+            Filter::Triangle => FilterType::Triangle,
+            Filter::CatmullRom => FilterType::CatmullRom,
+            Filter::Gaussian => FilterType::Gaussian,
+            Filter::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
+impl FromStr for Background {
+    type Err = String;
+
+    /// Accepts `checkerboard`, a few colour names or a `#rrggbb` hex colour
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "checkerboard" | "checker" => Ok(Background::Checkerboard),
+            "black" => Ok(Background::Solid([0x00, 0x00, 0x00])),
+            "white" => Ok(Background::Solid([0xff, 0xff, 0xff])),
+            "grey" | "gray" => Ok(Background::Solid([0x80, 0x80, 0x80])),
+            hex => {
+                let hex = hex.strip_prefix('#').unwrap_or(hex);
+                let channel = |index: usize| {
+                    hex.get(index * 2..index * 2 + 2)
+                        .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+                };
+                match (hex.len(), channel(0), channel(1), channel(2)) {
+                    (6, Some(red), Some(green), Some(blue)) => {
+                        Ok(Background::Solid([red, green, blue]))
+                    }
+                    _ => Err(format!("`{}` is not checkerboard, a colour name or #rrggbb", value)),
+                }
+            }
+        }
+    }
+}
+
+/// Everything that decides how the image appears in the window
+#[derive(Debug, Clone, Copy)]
+pub struct Frame<'a> {
+    pub image: &'a DynamicImage,
+    pub view: &'a View,
+    pub background: &'a Background,
+    pub filter: Filter,
+}
+
+/// Draws the image through the GPU renderer when it holds the image, otherwise on the CPU
+///
+/// The overlay, when given, is drawn on top either way.
 pub fn redraw_surface(
     pixels: &mut Pixels,
+    renderer: Option<&ImageRenderer>,
+    overlay: Option<&Overlay>,
     size: &PhysicalSize<u32>,
-    stream_image: &DynamicImage,
+    frame: &Frame,
 ) -> Result<()> {
-    if cfg!(debug_assertions) {
-        println!("Attempting resize on image");
+    if size.width == 0 || size.height == 0 {
+        return Ok(());
+    }
+
+    if let Some(renderer) = renderer.filter(|renderer| renderer.can_draw(frame.filter)) {
+        // The pixel buffer is uploaded on every render, so keep it as small as possible
+        let buffer_size = pixels.context().texture_extent;
+        if buffer_size.width != 1 || buffer_size.height != 1 {
+            pixels.resize_buffer(1, 1);
+        }
+        return retry_lost_surface(pixels, size, |pixels| {
+            renderer.render(pixels, size, frame, overlay)
+        });
     }
-    let image: DynamicImage = resize_image(stream_image, size.width, size.height);
 
-    // Use new build image to resize the pixels buffer
-    pixels.resize_buffer(image.width(), image.height());
+    // A buffer the size of the window is shown as is, rather than scaled by whole multiples
+    pixels.resize_buffer(size.width, size.height);
     pixels.resize_surface(size.width, size.height);
 
-    if cfg!(debug_assertions) {
-        println!("Converting image to rgb8");
-    }
-    let rgb8_image = image.into_rgb8();
-    let image_bytes: FlatSamples<&[u8]> = rgb8_image.as_flat_samples();
-    let image_bytes: &[u8] = image_bytes.as_slice();
-
-    image_bytes
-        .chunks_exact(3)
-        .zip(pixels.get_frame().chunks_exact_mut(4))
-        .for_each(|(image_pixel, pixel)| {
-            pixel[0] = image_pixel[0];
-            pixel[1] = image_pixel[1];
-            pixel[2] = image_pixel[2];
-            pixel[3] = 0xff;
-        });
+    render_frame(size, frame, pixels.get_frame());
 
     if cfg!(debug_assertions) {
         println!("Rendering pixels");
     }
-    pixels.render()?;
+    retry_lost_surface(pixels, size, |pixels| present(pixels, overlay))
+}
+
+/// Draws again after only the overlay changed, reusing the scaled image on the CPU path
+pub fn redraw_overlay(
+    pixels: &mut Pixels,
+    renderer: Option<&ImageRenderer>,
+    overlay: Option<&Overlay>,
+    size: &PhysicalSize<u32>,
+    frame: &Frame,
+) -> Result<()> {
+    if size.width == 0 || size.height == 0 {
+        return Ok(());
+    }
+
+    retry_lost_surface(pixels, size, |pixels| {
+        match renderer.filter(|renderer| renderer.can_draw(frame.filter)) {
+            Some(renderer) => renderer.render(pixels, size, frame, overlay),
+            None => present(pixels, overlay),
+        }
+    })
+}
+
+/// Runs `draw`, configuring the surface again and retrying once if it was lost or outdated
+///
+/// A frame that timed out is dropped, the next redraw replaces it anyway.
+fn retry_lost_surface(
+    pixels: &mut Pixels,
+    size: &PhysicalSize<u32>,
+    draw: impl Fn(&Pixels) -> Result<()>,
+) -> Result<()> {
+    match draw(pixels) {
+        Err(RviError::PixelsError(pixels::Error::Surface(
+            SurfaceError::Lost | SurfaceError::Outdated
+        ))) => {
+            if cfg!(debug_assertions) {
+                println!("Surface lost, configuring it again");
+            }
+            pixels.resize_surface(size.width, size.height);
+            draw(pixels)
+        }
+        Err(RviError::PixelsError(pixels::Error::Surface(SurfaceError::Timeout))) => Ok(()),
+        result => result,
+    }
+}
+
+/// Shows the pixel buffer as it is, with the overlay on top
+fn present(pixels: &Pixels, overlay: Option<&Overlay>) -> Result<()> {
+    pixels.render_with(|encoder, render_target, context| {
+        context.scaling_renderer.render(encoder, render_target);
+        if let Some(overlay) = overlay {
+            overlay.draw(encoder, render_target);
+        }
+        Ok(())
+    })?;
 
     Ok(())
 }
 
+/// Renders `frame` as the CPU path shows it in a window of `size`, without needing a window
+pub fn render_image(size: &PhysicalSize<u32>, frame: &Frame) -> RgbImage {
+    let mut buffer = RgbaImage::new(size.width, size.height);
+    render_frame(size, frame, &mut buffer);
+    DynamicImage::ImageRgba8(buffer).into_rgb8()
+}
+
+/// Draws the scaled image centred in a window sized RGBA `buffer`, letterboxed around it
+fn render_frame(size: &PhysicalSize<u32>, frame: &Frame, buffer: &mut [u8]) {
+    if cfg!(debug_assertions) {
+        println!("Attempting resize on image");
+    }
+    let image: DynamicImage = resize_image(frame.image, frame.view, size, frame.filter);
+
+    let [red, green, blue] = frame.background.letterbox();
+    for pixel in buffer.chunks_exact_mut(4) {
+        pixel.copy_from_slice(&[red, green, blue, 0xff]);
+    }
+
+    let left = size.width.saturating_sub(image.width()) / 2;
+    let top = size.height.saturating_sub(image.height()) / 2;
+    let start = ((top * size.width + left) * 4) as usize;
+    composite(image, frame.background, &mut buffer[start..], size.width);
+}
+
+/// Writes the image into an RGBA `frame` whose rows are `stride` pixels long, over
+/// `background` if it has alpha
+fn composite(image: DynamicImage, background: &Background, frame: &mut [u8], stride: u32) {
+    let width = image.width();
+    let rows = frame.chunks_mut((stride * 4) as usize);
+    if image.color().has_alpha() {
+        if cfg!(debug_assertions) {
+            println!("Compositing image over background");
+        }
+        let rgba8_image = image.into_rgba8();
+        let image_bytes: FlatSamples<&[u8]> = rgba8_image.as_flat_samples();
+        let image_bytes: &[u8] = image_bytes.as_slice();
+
+        image_bytes
+            .chunks_exact((width * 4) as usize)
+            .zip(rows)
+            .enumerate()
+            .for_each(|(y, (image_row, row))| {
+                image_row
+                    .chunks_exact(4)
+                    .zip(row.chunks_exact_mut(4))
+                    .enumerate()
+                    .for_each(|(x, (image_pixel, pixel))| {
+                        let behind = background.color_at(x as u32, y as u32);
+                        let alpha = image_pixel[3] as u32;
+                        for channel in 0..3 {
+                            let blended =
+                                (image_pixel[channel] as u32) * alpha +
+                                (behind[channel] as u32) * (0xff - alpha);
+                            pixel[channel] = ((blended + 0x7f) / 0xff) as u8;
+                        }
+                        pixel[3] = 0xff;
+                    });
+            });
+    } else {
+        if cfg!(debug_assertions) {
+            println!("Converting image to rgb8");
+        }
+        let rgb8_image = image.into_rgb8();
+        let image_bytes: FlatSamples<&[u8]> = rgb8_image.as_flat_samples();
+        let image_bytes: &[u8] = image_bytes.as_slice();
+
+        image_bytes
+            .chunks_exact((width * 3) as usize)
+            .zip(rows)
+            .for_each(|(image_row, row)| {
+                image_row
+                    .chunks_exact(3)
+                    .zip(row.chunks_exact_mut(4))
+                    .for_each(|(image_pixel, pixel)| {
+                        pixel[0] = image_pixel[0];
+                        pixel[1] = image_pixel[1];
+                        pixel[2] = image_pixel[2];
+                        pixel[3] = 0xff;
+                    });
+            });
+    }
+}
+
+/// Crops the image to the part visible through `view` and scales it for display
 pub fn resize_image(
-    image: &DynamicImage, 
-    width: u32, 
-    height: u32
+    image: &DynamicImage,
+    view: &View,
+    size: &PhysicalSize<u32>,
+    filter: Filter
 ) -> DynamicImage {
-    image.resize(width, height, FilterType::Nearest)
+    let image_size: [u32; 2] = [image.width(), image.height()];
+    let [x, y, width, height] = view.source_rect(image_size, size);
+    let output: PhysicalSize<u32> = view.output_size(image_size, size);
+    let filter: FilterType = filter.filter_type(view.scale(image_size, size));
+
+    image
+        .crop_imm(x, y, width, height)
+        .resize_exact(output.width, output.height, filter)
 }
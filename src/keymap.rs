@@ -0,0 +1,335 @@
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use winit::event::{ ModifiersState, VirtualKeyCode };
+
+/// Something a key can be bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum)]
+pub enum Action {
+    /// Leaves fullscreen first, then quits
+    Quit,
+    Reload,
+    Next,
+    Previous,
+    First,
+    Last,
+    ZoomIn,
+    ZoomOut,
+    ResetView,
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    TogglePause,
+    NextFrame,
+    PreviousFrame,
+    Faster,
+    Slower,
+    ResetSpeed,
+    RotateClockwise,
+    RotateCounterClockwise,
+    FlipHorizontal,
+    FlipVertical,
+    CycleBackground,
+    CycleFilter,
+    /// Switches between contain, cover, fit width and height, actual size and integer scaling
+    CycleFit,
+    ToggleFullscreen,
+    /// Moves the window, or fullscreen, to the next monitor and fits it there
+    NextMonitor,
+    ToggleSlideshow,
+    ToggleOverlay,
+    /// Shows the position and colour of the pixel under the cursor
+    ToggleInspector,
+    /// Prints the inspected colour and copies it to the clipboard
+    CopyColor,
+}
+
+impl Action {
+    pub fn name(&self) -> &'static str {
+        self.to_possible_value().map(|value| value.get_name()).unwrap_or_default()
+    }
+}
+
+/// Names keys are written as in the config file, the first name for a key is used for display
+const KEY_NAMES: &[(&str, VirtualKeyCode)] = &[
+    ("a", VirtualKeyCode::A),
+    ("b", VirtualKeyCode::B),
+    ("c", VirtualKeyCode::C),
+    ("d", VirtualKeyCode::D),
+    ("e", VirtualKeyCode::E),
+    ("f", VirtualKeyCode::F),
+    ("g", VirtualKeyCode::G),
+    ("h", VirtualKeyCode::H),
+    ("i", VirtualKeyCode::I),
+    ("j", VirtualKeyCode::J),
+    ("k", VirtualKeyCode::K),
+    ("l", VirtualKeyCode::L),
+    ("m", VirtualKeyCode::M),
+    ("n", VirtualKeyCode::N),
+    ("o", VirtualKeyCode::O),
+    ("p", VirtualKeyCode::P),
+    ("q", VirtualKeyCode::Q),
+    ("r", VirtualKeyCode::R),
+    ("s", VirtualKeyCode::S),
+    ("t", VirtualKeyCode::T),
+    ("u", VirtualKeyCode::U),
+    ("v", VirtualKeyCode::V),
+    ("w", VirtualKeyCode::W),
+    ("x", VirtualKeyCode::X),
+    ("y", VirtualKeyCode::Y),
+    ("z", VirtualKeyCode::Z),
+    ("0", VirtualKeyCode::Key0),
+    ("1", VirtualKeyCode::Key1),
+    ("2", VirtualKeyCode::Key2),
+    ("3", VirtualKeyCode::Key3),
+    ("4", VirtualKeyCode::Key4),
+    ("5", VirtualKeyCode::Key5),
+    ("6", VirtualKeyCode::Key6),
+    ("7", VirtualKeyCode::Key7),
+    ("8", VirtualKeyCode::Key8),
+    ("9", VirtualKeyCode::Key9),
+    ("f1", VirtualKeyCode::F1),
+    ("f2", VirtualKeyCode::F2),
+    ("f3", VirtualKeyCode::F3),
+    ("f4", VirtualKeyCode::F4),
+    ("f5", VirtualKeyCode::F5),
+    ("f6", VirtualKeyCode::F6),
+    ("f7", VirtualKeyCode::F7),
+    ("f8", VirtualKeyCode::F8),
+    ("f9", VirtualKeyCode::F9),
+    ("f10", VirtualKeyCode::F10),
+    ("f11", VirtualKeyCode::F11),
+    ("f12", VirtualKeyCode::F12),
+    ("escape", VirtualKeyCode::Escape),
+    ("esc", VirtualKeyCode::Escape),
+    ("space", VirtualKeyCode::Space),
+    ("enter", VirtualKeyCode::Return),
+    ("return", VirtualKeyCode::Return),
+    ("backspace", VirtualKeyCode::Back),
+    ("tab", VirtualKeyCode::Tab),
+    ("left", VirtualKeyCode::Left),
+    ("right", VirtualKeyCode::Right),
+    ("up", VirtualKeyCode::Up),
+    ("down", VirtualKeyCode::Down),
+    ("home", VirtualKeyCode::Home),
+    ("end", VirtualKeyCode::End),
+    ("pageup", VirtualKeyCode::PageUp),
+    ("pagedown", VirtualKeyCode::PageDown),
+    ("insert", VirtualKeyCode::Insert),
+    ("delete", VirtualKeyCode::Delete),
+    ("+", VirtualKeyCode::Plus),
+    ("plus", VirtualKeyCode::Plus),
+    ("-", VirtualKeyCode::Minus),
+    ("minus", VirtualKeyCode::Minus),
+    ("=", VirtualKeyCode::Equals),
+    (",", VirtualKeyCode::Comma),
+    (".", VirtualKeyCode::Period),
+    ("/", VirtualKeyCode::Slash),
+    ("\\", VirtualKeyCode::Backslash),
+    ("[", VirtualKeyCode::LBracket),
+    ("]", VirtualKeyCode::RBracket),
+    (";", VirtualKeyCode::Semicolon),
+    ("'", VirtualKeyCode::Apostrophe),
+    ("`", VirtualKeyCode::Grave),
+    ("numpad0", VirtualKeyCode::Numpad0),
+    ("numpad1", VirtualKeyCode::Numpad1),
+    ("numpad2", VirtualKeyCode::Numpad2),
+    ("numpad3", VirtualKeyCode::Numpad3),
+    ("numpad4", VirtualKeyCode::Numpad4),
+    ("numpad5", VirtualKeyCode::Numpad5),
+    ("numpad6", VirtualKeyCode::Numpad6),
+    ("numpad7", VirtualKeyCode::Numpad7),
+    ("numpad8", VirtualKeyCode::Numpad8),
+    ("numpad9", VirtualKeyCode::Numpad9),
+    ("numpad+", VirtualKeyCode::NumpadAdd),
+    ("numpad-", VirtualKeyCode::NumpadSubtract),
+    ("numpad*", VirtualKeyCode::NumpadMultiply),
+    ("numpad/", VirtualKeyCode::NumpadDivide),
+    ("numpadenter", VirtualKeyCode::NumpadEnter),
+];
+
+const MODIFIER_NAMES: &[(&str, ModifiersState)] = &[
+    ("ctrl", ModifiersState::CTRL),
+    ("control", ModifiersState::CTRL),
+    ("alt", ModifiersState::ALT),
+    ("shift", ModifiersState::SHIFT),
+    ("super", ModifiersState::LOGO),
+    ("logo", ModifiersState::LOGO),
+];
+
+/// A key along with the modifiers that must be held, written like `ctrl+shift+r`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    key: VirtualKeyCode,
+    modifiers: ModifiersState,
+}
+
+impl KeyBinding {
+    pub fn new(key: VirtualKeyCode, modifiers: ModifiersState) -> KeyBinding {
+        KeyBinding { key, modifiers }
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let lower = value.trim().to_ascii_lowercase();
+        // `+` separates modifiers but also ends names like `numpad+`, so take the longest key
+        // name that follows a separator
+        let (key, modifiers) = KEY_NAMES
+            .iter()
+            .filter_map(|(name, key)| {
+                let modifiers = lower.strip_suffix(name)?;
+                match modifiers.strip_suffix('+') {
+                    Some(modifiers) => Some((name.len(), *key, modifiers)),
+                    None if modifiers.is_empty() => Some((name.len(), *key, modifiers)),
+                    None => None,
+                }
+            })
+            .max_by_key(|(length, _, _)| *length)
+            .map(|(_, key, modifiers)| (key, modifiers))
+            .ok_or_else(|| {
+                let key = lower.rsplit_once('+').map_or(lower.as_str(), |(_, key)| key);
+                format!("`{}` is not a known key", key)
+            })?;
+
+        let mut state = ModifiersState::empty();
+        for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+            state |= MODIFIER_NAMES
+                .iter()
+                .find(|(name, _)| *name == modifier)
+                .map(|(_, state)| *state)
+                .ok_or_else(|| format!("`{}` is not a known modifier in `{}`", modifier, value))?;
+        }
+
+        Ok(KeyBinding::new(key, state))
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, state) in [
+            ("ctrl", ModifiersState::CTRL),
+            ("alt", ModifiersState::ALT),
+            ("shift", ModifiersState::SHIFT),
+            ("super", ModifiersState::LOGO),
+        ] {
+            if self.modifiers.contains(state) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match KEY_NAMES.iter().find(|(_, key)| *key == self.key) {
+            Some((name, _)) => write!(f, "{}", name),
+            None => write!(f, "{:?}", self.key),
+        }
+    }
+}
+
+/// Which action each key binding triggers
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeyBinding, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let none = ModifiersState::empty();
+        let bindings = [
+            (VirtualKeyCode::Escape, Action::Quit),
+            (VirtualKeyCode::R, Action::Reload),
+            (VirtualKeyCode::N, Action::Next),
+            (VirtualKeyCode::Space, Action::Next),
+            (VirtualKeyCode::P, Action::Previous),
+            (VirtualKeyCode::Back, Action::Previous),
+            (VirtualKeyCode::Home, Action::First),
+            (VirtualKeyCode::End, Action::Last),
+            (VirtualKeyCode::Plus, Action::ZoomIn),
+            (VirtualKeyCode::Equals, Action::ZoomIn),
+            (VirtualKeyCode::NumpadAdd, Action::ZoomIn),
+            (VirtualKeyCode::Minus, Action::ZoomOut),
+            (VirtualKeyCode::NumpadSubtract, Action::ZoomOut),
+            (VirtualKeyCode::Key0, Action::ResetView),
+            (VirtualKeyCode::Numpad0, Action::ResetView),
+            (VirtualKeyCode::Left, Action::PanLeft),
+            (VirtualKeyCode::Right, Action::PanRight),
+            (VirtualKeyCode::Up, Action::PanUp),
+            (VirtualKeyCode::Down, Action::PanDown),
+            (VirtualKeyCode::K, Action::TogglePause),
+            (VirtualKeyCode::Period, Action::NextFrame),
+            (VirtualKeyCode::Comma, Action::PreviousFrame),
+            (VirtualKeyCode::RBracket, Action::Faster),
+            (VirtualKeyCode::LBracket, Action::Slower),
+            (VirtualKeyCode::Backslash, Action::ResetSpeed),
+            (VirtualKeyCode::E, Action::RotateClockwise),
+            (VirtualKeyCode::Q, Action::RotateCounterClockwise),
+            (VirtualKeyCode::H, Action::FlipHorizontal),
+            (VirtualKeyCode::V, Action::FlipVertical),
+            (VirtualKeyCode::B, Action::CycleBackground),
+            (VirtualKeyCode::I, Action::CycleFilter),
+            (VirtualKeyCode::Z, Action::CycleFit),
+            (VirtualKeyCode::F, Action::ToggleFullscreen),
+            (VirtualKeyCode::F11, Action::ToggleFullscreen),
+            (VirtualKeyCode::M, Action::NextMonitor),
+            (VirtualKeyCode::S, Action::ToggleSlideshow),
+            (VirtualKeyCode::O, Action::ToggleOverlay),
+            (VirtualKeyCode::C, Action::ToggleInspector),
+        ]
+        .into_iter()
+        .map(|(key, action)| (KeyBinding::new(key, none), action))
+        .chain([
+            (KeyBinding::new(VirtualKeyCode::C, ModifiersState::CTRL), Action::CopyColor),
+        ])
+        .collect();
+        Keymap { bindings }
+    }
+}
+
+impl Keymap {
+    /// Binds `binding` to `action`, or removes the binding when `action` is `None`
+    pub fn bind(&mut self, binding: KeyBinding, action: Option<Action>) {
+        match action {
+            Some(action) => self.bindings.insert(binding, action),
+            None => self.bindings.remove(&binding),
+        };
+    }
+
+    /// Applies a config file's `[keys]` table, where the action `none` unbinds the key
+    pub fn apply(&mut self, keys: HashMap<String, String>) -> Result<(), String> {
+        for (binding, action) in keys {
+            let binding: KeyBinding = binding.parse()?;
+            let action = match action.as_str() {
+                "none" => None,
+                action => Some(Action::from_str(action, true)?),
+            };
+            self.bind(binding, action);
+        }
+        Ok(())
+    }
+
+    /// Looks up a key press, letting shift through unbound since some keys need it to type
+    pub fn action(&self, key: VirtualKeyCode, modifiers: ModifiersState) -> Option<Action> {
+        self.bindings
+            .get(&KeyBinding::new(key, modifiers))
+            .or_else(|| self.bindings.get(&KeyBinding::new(key, modifiers - ModifiersState::SHIFT)))
+            .copied()
+    }
+}
+
+/// Lists every binding grouped by action, in the format the config file accepts
+impl fmt::Display for Keymap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut bindings: Vec<(Action, String)> = self.bindings
+            .iter()
+            .map(|(binding, action)| (*action, binding.to_string()))
+            .collect();
+        bindings.sort();
+        writeln!(f, "[keys]")?;
+        for (action, binding) in bindings {
+            writeln!(f, "{:<16} = \"{}\"", format!("{:?}", binding), action.name())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,25 @@
+//! Core of the riv image viewer: decoding, view maths and frame composition
+//!
+//! The `riv` binary is a thin winit shell around these modules, and the same pieces can render
+//! images without a window, see [`export::export`] and [`graphics::render_image`].
+
+pub mod animation;
+pub mod config;
+pub mod errors;
+pub mod events;
+pub mod export;
+pub mod gpu;
+pub mod graphics;
+pub mod keymap;
+pub mod loader;
+pub mod overlay;
+pub mod playlist;
+pub mod renderer;
+pub mod slideshow;
+pub mod software;
+pub mod transform;
+pub mod view;
+pub mod watcher;
+pub mod window;
+
+pub use errors::{ Result, RviError };
@@ -0,0 +1,169 @@
+use super::animation::Animation;
+use super::errors::Result;
+use super::events::RivEvent;
+use super::playlist::is_stdin;
+use super::transform::{ read_orientation, Transform };
+use image::error::{ DecodingError, ImageError, ImageFormatHint };
+use image::io::Reader;
+use std::fs::File;
+use std::io::{ BufRead, BufReader, Cursor, Read, Seek, SeekFrom };
+use std::path::{ Path, PathBuf };
+use std::sync::atomic::{ AtomicU64, Ordering };
+use std::sync::{ Arc, OnceLock };
+use tokio::runtime::{ Builder, Runtime };
+use winit::event_loop::EventLoopProxy;
+
+/// Decodes images on background threads and hands them to the event loop as `RivEvent::Loaded`
+#[derive(Debug)]
+pub struct Loader {
+    runtime: Runtime,
+    proxy: EventLoopProxy<RivEvent>,
+    /// Whether to rotate images upright according to their EXIF orientation
+    auto_orient: bool,
+    /// Standard input can only be read once, so keep the bytes to show it again later
+    stdin: Arc<OnceLock<Vec<u8>>>,
+    /// Bumped by every `load`, so decodes can tell they have been superseded
+    generation: Arc<AtomicU64>,
+    loading: bool,
+}
+
+impl Loader {
+    pub fn new(proxy: EventLoopProxy<RivEvent>, auto_orient: bool) -> Result<Loader> {
+        let runtime: Runtime = Builder::new_current_thread()
+            .thread_name("riv-loader")
+            // Only the newest load matters, so a couple of threads covers a stale one finishing
+            .max_blocking_threads(2)
+            .build()?;
+
+        Ok(Loader {
+            runtime,
+            proxy,
+            auto_orient,
+            stdin: Arc::new(OnceLock::new()),
+            generation: Arc::new(AtomicU64::new(0)),
+            loading: false,
+        })
+    }
+
+    /// Starts decoding `path`, superseding any load still in flight
+    pub fn load(&mut self, path: &Path) {
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        self.loading = true;
+
+        let current = self.generation.clone();
+        let path: PathBuf = path.to_path_buf();
+        let auto_orient = self.auto_orient;
+        let proxy = self.proxy.clone();
+        let stdin = self.stdin.clone();
+
+        self.runtime.spawn_blocking(move || {
+            let stale = || current.load(Ordering::SeqCst) != generation;
+            if stale() {
+                return;
+            }
+            let result = if is_stdin(&path) {
+                read_stdin(&stdin).and_then(|bytes| load_animation(Cursor::new(bytes), auto_orient))
+            } else {
+                load_file(&path, auto_orient)
+            };
+            if stale() {
+                return;
+            }
+            // The event loop is gone when the window closed mid load, so nobody is waiting
+            let _ = proxy.send_event(RivEvent::Loaded { generation, result });
+        });
+    }
+
+    /// Accepts a finished load, returning false for results superseded by a newer `load`
+    pub fn finish(&mut self, generation: u64) -> bool {
+        if generation != self.generation.load(Ordering::SeqCst) {
+            return false;
+        }
+        self.loading = false;
+        true
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.loading
+    }
+}
+
+/// Decodes `path` on the calling thread, reading standard input for `-`
+pub fn load_now(path: &Path, auto_orient: bool) -> Result<Animation> {
+    if is_stdin(path) {
+        let mut bytes: Vec<u8> = Vec::new();
+        std::io::stdin().lock().read_to_end(&mut bytes)?;
+        return load_animation(Cursor::new(bytes), auto_orient);
+    }
+    load_file(path, auto_orient)
+}
+
+fn load_file(path: &Path, auto_orient: bool) -> Result<Animation> {
+    load_animation(BufReader::new(File::open(path)?), auto_orient)
+}
+
+/// Decodes every frame of an image, turning it upright when `auto_orient` is set
+pub fn load_animation<R: BufRead + Seek>(mut reader: R, auto_orient: bool) -> Result<Animation> {
+    let file_size: u64 = reader.seek(SeekFrom::End(0))?;
+    reader.rewind()?;
+    let orientation: Transform = if auto_orient {
+        let orientation = read_orientation(&mut reader);
+        reader.rewind()?;
+        orientation
+    } else {
+        Transform::default()
+    };
+
+    let reader = Reader::new(reader).with_guessed_format()?;
+    let format = reader.format();
+    let mut animation: Animation = Animation::decode(reader)?;
+    let image = animation.image();
+    if image.width() == 0 || image.height() == 0 {
+        // Nothing could be drawn, and the view maths assume at least one pixel
+        let format = format.map_or(ImageFormatHint::Unknown, ImageFormatHint::Exact);
+        return Err(ImageError::Decoding(DecodingError::new(format, "the image is empty")).into());
+    }
+    if !orientation.is_identity() {
+        animation.map_frames(|frame| orientation.apply(frame));
+    }
+    animation.set_file_size(file_size);
+
+    Ok(animation)
+}
+
+/// Reads all of standard input the first time it is needed
+fn read_stdin(stdin: &OnceLock<Vec<u8>>) -> Result<&[u8]> {
+    if let Some(bytes) = stdin.get() {
+        return Ok(bytes);
+    }
+
+    if cfg!(debug_assertions) {
+        println!("Reading image from standard input");
+    }
+    let mut bytes: Vec<u8> = Vec::new();
+    std::io::stdin().lock().read_to_end(&mut bytes)?;
+    Ok(stdin.get_or_init(|| bytes))
+}
+
+/// Reads just the size of `path` from its header, as it will display once upright
+pub fn read_dimensions(path: &Path, auto_orient: bool) -> Option<[u32; 2]> {
+    if is_stdin(path) {
+        return None;
+    }
+
+    let (width, height) = Reader::open(path)
+        .ok()?
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()?;
+    let transposed = || {
+        let mut file = BufReader::new(File::open(path).ok()?);
+        Some(read_orientation(&mut file).is_transposed())
+    };
+    if auto_orient && transposed().unwrap_or(false) {
+        Some([height, width])
+    } else {
+        Some([width, height])
+    }
+}
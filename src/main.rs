@@ -1,33 +1,71 @@
-use std::option_env;
+use std::io::IsTerminal;
 use std::time::{ Instant, Duration };
 
 use image::DynamicImage;
-use pixels::wgpu::RequestAdapterOptions;
-use pixels::{ Pixels, PixelsBuilder, SurfaceTexture };
 use winit::{
-    dpi::PhysicalSize,
-    event::{ ElementState, KeyboardInput, VirtualKeyCode },
+    dpi::{ PhysicalPosition, PhysicalSize },
+    event::{
+        ElementState,
+        KeyboardInput,
+        ModifiersState,
+        MouseButton,
+        MouseScrollDelta,
+    },
     event_loop::ControlFlow,
-    window::Window,
+    monitor::MonitorHandle,
 };
 
-use clap::Parser;
+use clap::{ error::ErrorKind, CommandFactory };
 
-mod config;
-mod errors;
-mod events;
-mod graphics;
-mod window;
+use riv::animation::Animation;
+use riv::config::Config;
+use riv::errors::{ Result, RviError };
+use riv::events::{ create_event_loop, RivEvent };
+use riv::export;
+use riv::graphics::{ Background, Filter, Frame };
+use riv::keymap::Action;
+use riv::loader::{ read_dimensions, Loader };
+use riv::overlay::{ Inspection, Status };
+use riv::playlist::{ display_name, Playlist, Step, STDIN };
+use riv::renderer::{ create_renderer, Renderer };
+use riv::slideshow::{ Slideshow, DEFAULT_INTERVAL };
+use riv::transform::Transform;
+use riv::view::{ aspect_size, fit_window_size, Fit, View };
+use riv::watcher::FileWatcher;
+use riv::window::{
+    create_window,
+    find_monitor,
+    move_to_monitor,
+    next_monitor,
+    refit_size,
+    refit_window,
+    toggle_fullscreen,
+    DEFAULT_SCREEN_SIZE,
+};
 
-use crate::config::Config;
-use crate::errors::Result;
-use crate::events::create_event_loop;
-use crate::graphics::redraw_surface;
-use crate::window::{ get_screen_size, create_window };
+/// Window size to start with when the first image's header can't be read
+const PLACEHOLDER_SIZE: [u32; 2] = [640, 480];
+/// Zoom multiplier per key press or wheel notch
+const ZOOM_STEP: f32 = 1.25;
+/// Fraction of the window moved per arrow key press
+const PAN_STEP: f32 = 0.1;
+/// Quiet time after a watched file changes before it is reloaded
+const RELOAD_DELAY: Duration = Duration::from_millis(200);
+/// Quiet time after the window is resized before it snaps to the image's aspect ratio
+const SNAP_DELAY: Duration = Duration::from_millis(300);
+/// Playback speed multiplier per key press
+const SPEED_STEP: f32 = 2.0;
+/// How long an error stays on screen
+const MESSAGE_DURATION: Duration = Duration::from_secs(5);
 
-const SCREEN_PERCENT: u32 = 90;
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error: {}", err.describe());
+        std::process::exit(err.exit_code());
+    }
+}
 
-fn main() -> Result<()> {
+fn run() -> Result<()> {
     let mut resize_requested = false;
     let mut last_resize = Instant::now();
     let debounce_duration = Duration::from_millis(100);
@@ -35,113 +73,157 @@ fn main() -> Result<()> {
     if cfg!(debug_assertions) {
         std::env::set_var("RUST_BACKTRACE", "full");
     }
-    let config: Config = Config::parse();
+    let mut config: Config = Config::load()?;
+    if config.print_keymap {
+        print!("{}", config.keymap);
+        return Ok(());
+    }
+    if config.file_names.is_empty() {
+        if std::io::stdin().is_terminal() {
+            Config::command()
+                .error(ErrorKind::MissingRequiredArgument, "no images given to open")
+                .exit();
+        }
+        config.file_names.push(STDIN.to_string());
+    }
 
-    if cfg!(debug_assertions) {
-        println!("Fetching and decoding stream image");
+    let mut playlist: Playlist = Playlist::from_args(&config.file_names)?;
+    if let Some(output) = &config.output {
+        return export::export(&config, playlist.current(), output);
+    }
+    if config.shuffle {
+        playlist.shuffle();
     }
-    let stream_image: DynamicImage = image::io::Reader
-        ::open(&config.file_name)?
-        .with_guessed_format()?
-        .decode()?;
+    let mut transform: Transform = Transform::from_degrees(config.rotate);
+    let auto_orient: bool = !config.no_auto_orient;
 
     let event_loop = create_event_loop();
 
-    let screen_size: PhysicalSize<u32> = match get_screen_size(&event_loop) {
-        Ok(screen_size) => screen_size,
-        Err(_) => {
-            let ss: (u32, u32) = match option_env!("SCREEN_SIZE") as Option<&str> {
-                Some(ss) => {
-                    let ss: Vec<&str> = ss.splitn(2, ",").collect();
-                    (ss[0].parse().unwrap(), ss[1].parse().unwrap())
-                }
-                None => (640, 480),
-            };
-            PhysicalSize::new(ss.0, ss.1)
-        }
+    // Decode in the background and open the window straight away, sized from the header
+    let mut loader: Loader = Loader::new(event_loop.create_proxy(), auto_orient)?;
+    loader.load(playlist.current());
+    // How the playlist moved for the load in flight, or none when reloading the same image
+    let mut loading_step: Option<Step> = Some(Step::First);
+    let mut refit_on_load = true;
+
+    let mut watcher: Option<FileWatcher> = if config.watch {
+        Some(FileWatcher::new(event_loop.create_proxy())?)
+    } else {
+        None
     };
+    let mut reload_at: Option<Instant> = None;
+    let mut snap_at: Option<Instant> = None;
+    let mut slideshow = Slideshow::new(
+        config.slideshow.unwrap_or(DEFAULT_INTERVAL),
+        config.slideshow.is_some()
+    );
+
+    let mut image_size: [u32; 2] = read_dimensions(playlist.current(), auto_orient)
+        .unwrap_or(PLACEHOLDER_SIZE);
+    if transform.is_transposed() {
+        image_size.swap(0, 1);
+    }
+    let mut animation: Animation = Animation::placeholder(image_size);
+
+    let monitor: Option<MonitorHandle> = find_monitor(&event_loop, config.monitor.as_deref())?;
+    let mut screen_size: PhysicalSize<u32> = config.screen_size
+        .or_else(|| monitor.as_ref().map(MonitorHandle::size))
+        .unwrap_or_else(|| {
+            eprintln!(
+                "{}, assuming {}x{}, pass --screen-size WxH to set it",
+                RviError::NoPrimaryMonitor,
+                DEFAULT_SCREEN_SIZE.width,
+                DEFAULT_SCREEN_SIZE.height
+            );
+            DEFAULT_SCREEN_SIZE
+        });
 
     if cfg!(debug_assertions) {
         dbg!(screen_size);
     }
 
-    let mut scale: [f32; 2] = [
-        calc_scale_factor(
-            &((screen_size.width * SCREEN_PERCENT) / 100),
-            &stream_image.width(),
-            Some(config.up_scale)
-        ),
-        calc_scale_factor(
-            &((screen_size.height * SCREEN_PERCENT) / 100),
-            &stream_image.height(),
-            Some(config.up_scale)
-        ),
-    ];
-
-    float_ord::sort(&mut scale);
-
-    let scale: f32 = scale[1];
-
-    let window_inner_size: PhysicalSize<u32> = PhysicalSize::new(
-        ((stream_image.width() as f32) / scale).ceil() as u32,
-        ((stream_image.height() as f32) / scale).ceil() as u32
-    );
+    let window_inner_size: PhysicalSize<u32> =
+        fit_window_size(&screen_size, image_size, config.screen_percent, config.up_scale);
 
     if cfg!(debug_assertions) {
         println!("Creating a new window");
     }
 
-    let window = create_window(&event_loop, window_inner_size)?;
+    let window = create_window(&event_loop, window_inner_size, monitor.as_ref(), &config)?;
+    // Fullscreen windows ignore the requested size
+    let window_inner_size: PhysicalSize<u32> = window.inner_size();
+    let mut title: String =
+        window_title(&playlist, &animation, config.fit, config.filter, &slideshow, true);
+    window.set_title(&title);
 
-    let surface: SurfaceTexture<Window> = SurfaceTexture::new(
-        window_inner_size.width,
-        window_inner_size.height,
-        &window
-    );
+    let mut renderer: Box<dyn Renderer> = create_renderer(&window, window_inner_size, &config)?;
+    renderer.upload(animation.image());
 
-    if cfg!(debug_assertions) {
-        println!("Building initial pixels with low performance mode as:");
-        dbg!(config.low_performance_mode);
-        // Enumerate adapters
-        let instance = pixels::wgpu::Instance::new(pixels::wgpu::Backends::all());
-        for adapter in instance.enumerate_adapters(pixels::wgpu::Backends::all()) {
-            dbg!(adapter);
-        }
-    }
-    let mut pixels: Pixels = PixelsBuilder::new(
-        window_inner_size.width,
-        window_inner_size.height,
-        surface
-    )
-        .device_descriptor(pixels::wgpu::DeviceDescriptor {
-            features: pixels::wgpu::Features::empty(),
-            limits: pixels::wgpu::Limits::default(),
-            label: None,
-        })
-        .request_adapter_options(RequestAdapterOptions {
-            power_preference: if config.low_performance_mode {
-                pixels::wgpu::PowerPreference::default()
-            } else {
-                pixels::wgpu::PowerPreference::HighPerformance
-            },
-            compatible_surface: None,
-            force_fallback_adapter: false,
-        })
-        .wgpu_backend(pixels::wgpu::Backends::all())
-        .enable_vsync(false)
-        .build()?;
-
-    redraw_surface(&mut pixels, &window_inner_size, &stream_image)?;
+    let mut view: View = View::new(config.fit);
+    let backgrounds: Vec<Background> = Background::cycle(config.background);
+    let mut background: usize = 0;
+    let mut filter: Filter = config.filter;
+    let mut cursor: PhysicalPosition<f64> = PhysicalPosition::new(0.0, 0.0);
+    let mut dragging = false;
+    let mut modifiers = ModifiersState::empty();
+    let mut redraw_requested = false;
+    let mut image_changed = false;
+
+    let mut show_overlay: bool = config.overlay;
+    let mut inspecting = false;
+    // Only the overlay needs drawing again, the image itself is unchanged
+    let mut overlay_changed = false;
+    // Created on first use, and kept since some platforms drop the contents along with it
+    let mut clipboard: Option<arboard::Clipboard> = None;
+    // Error shown over the image, and when it goes away unless nothing else can be shown
+    let mut message: Option<(String, Option<Instant>)> = None;
+
+    renderer.draw(
+        &window_inner_size,
+        &Frame {
+            image: animation.image(),
+            view: &view,
+            background: &backgrounds[background],
+            filter,
+        },
+        "",
+        false
+    )?;
 
     event_loop.run(move |event, _, control_flow| {
-        *control_flow = ControlFlow::Wait;
+        // Wake up for whichever comes first of the next animation frame or slide, or a
+        // debounced resize or reload
+        let resize_deadline = Some(last_resize + debounce_duration).filter(|_| resize_requested);
+        let deadlines = resize_deadline
+            .into_iter()
+            .chain(animation.deadline())
+            .chain(slideshow.deadline())
+            .chain(reload_at)
+            .chain(snap_at)
+            .chain(message.as_ref().and_then(|(_, expires)| *expires));
+        *control_flow = match deadlines.min() {
+            // Draws asked for after the events were cleared still need a pass of the loop
+            _ if overlay_changed || redraw_requested => ControlFlow::Poll,
+            Some(deadline) => ControlFlow::WaitUntil(deadline),
+            None => ControlFlow::Wait,
+        };
 
         match event {
             winit::event::Event::WindowEvent { window_id, event } if window_id == window.id() =>
                 match event {
-                    winit::event::WindowEvent::Resized(_) => {
-                        last_resize = Instant::now();
-                        resize_requested = true;
+                    winit::event::WindowEvent::Resized(size) => {
+                        renderer.resize(&size);
+                        if renderer.is_fast(filter) {
+                            // Scaling on the GPU is cheap enough to follow the resize live
+                            redraw_requested = true;
+                        } else {
+                            last_resize = Instant::now();
+                            resize_requested = true;
+                        }
+                        if config.snap_aspect {
+                            // Wait for the drag to finish rather than fight it
+                            snap_at = Some(Instant::now() + SNAP_DELAY);
+                        }
                     }
                     winit::event::WindowEvent::CloseRequested => {
                         *control_flow = ControlFlow::Exit;
@@ -149,39 +231,493 @@ fn main() -> Result<()> {
                     winit::event::WindowEvent::KeyboardInput {
                         input: KeyboardInput { state: ElementState::Pressed, virtual_keycode, .. },
                         ..
-                    } =>
-                        match virtual_keycode {
-                            Some(VirtualKeyCode::Escape) => {
+                    } => {
+                        let action = match virtual_keycode
+                            .and_then(|key| config.keymap.action(key, modifiers))
+                        {
+                            Some(action) => action,
+                            None => return,
+                        };
+                        if action != Action::ToggleSlideshow && slideshow.is_running() {
+                            // Someone is looking closely, so hold the current image
+                            slideshow.stop();
+                        }
+                        let image_size = [animation.image().width(), animation.image().height()];
+                        let size = window.inner_size();
+                        let pan_x = (size.width as f32) * PAN_STEP;
+                        let pan_y = (size.height as f32) * PAN_STEP;
+                        let mut refit = false;
+
+                        match action {
+                            Action::Quit if window.fullscreen().is_some() => {
+                                toggle_fullscreen(&window);
+                                refit = true;
+                            }
+                            Action::Quit => {
                                 *control_flow = ControlFlow::Exit;
                             }
-                            Some(VirtualKeyCode::R) => {
-                                redraw_surface(
-                                    &mut pixels,
-                                    &window.inner_size(),
-                                    &stream_image
-                                ).unwrap();
+                            Action::ToggleFullscreen => {
+                                toggle_fullscreen(&window);
+                                refit = true;
+                            }
+                            Action::NextMonitor => {
+                                if let Some(monitor) = next_monitor(&window) {
+                                    let fitted = refit_size(
+                                        &window,
+                                        Some(&monitor),
+                                        &mut screen_size,
+                                        image_size,
+                                        &config
+                                    );
+                                    move_to_monitor(&window, &monitor, fitted, config.position);
+                                    view.reset();
+                                    redraw_requested = true;
+                                }
+                            }
+                            Action::ToggleSlideshow => {
+                                slideshow.toggle();
+                            }
+                            Action::ToggleOverlay => {
+                                show_overlay = !show_overlay;
+                                overlay_changed = true;
+                            }
+                            Action::ToggleInspector => {
+                                inspecting = !inspecting;
+                                overlay_changed = true;
+                            }
+                            Action::CopyColor => {
+                                let inspection =
+                                    Inspection::at(animation.image(), &view, cursor, &size);
+                                if let Some(inspection) = inspection {
+                                    let hex = inspection.hex();
+                                    println!("{}", hex);
+                                    if clipboard.is_none() {
+                                        match arboard::Clipboard::new() {
+                                            Ok(created) => clipboard = Some(created),
+                                            Err(err) => message = Some((
+                                                format!("No clipboard: {}", err),
+                                                Some(Instant::now() + MESSAGE_DURATION)
+                                            )),
+                                        }
+                                    }
+                                    if let Some(clipboard) = clipboard.as_mut() {
+                                        if let Err(err) = clipboard.set_text(hex) {
+                                            message = Some((
+                                                format!("Unable to copy: {}", err),
+                                                Some(Instant::now() + MESSAGE_DURATION)
+                                            ));
+                                        }
+                                    }
+                                    overlay_changed |= message.is_some();
+                                }
+                            }
+                            Action::Reload => {
+                                loading_step = None;
+                                loader.load(playlist.current());
+                            }
+                            Action::ZoomIn => {
+                                view.zoom_by(ZOOM_STEP, None, image_size, &size);
+                                redraw_requested = true;
+                            }
+                            Action::ZoomOut => {
+                                view.zoom_by(1.0 / ZOOM_STEP, None, image_size, &size);
+                                redraw_requested = true;
+                            }
+                            Action::ResetView => {
+                                view.reset();
+                                redraw_requested = true;
+                            }
+                            Action::TogglePause => {
+                                animation.toggle_pause();
+                            }
+                            Action::NextFrame => {
+                                animation.step(true);
+                                image_changed = true;
+                                redraw_requested = true;
+                            }
+                            Action::PreviousFrame => {
+                                animation.step(false);
+                                image_changed = true;
+                                redraw_requested = true;
+                            }
+                            Action::Faster => {
+                                animation.change_speed(SPEED_STEP);
+                            }
+                            Action::Slower => {
+                                animation.change_speed(1.0 / SPEED_STEP);
+                            }
+                            Action::ResetSpeed => {
+                                animation.reset_speed();
+                            }
+                            Action::CycleBackground => {
+                                background = (background + 1) % backgrounds.len();
+                                redraw_requested = true;
+                            }
+                            Action::CycleFilter => {
+                                filter = filter.next();
+                                redraw_requested = true;
+                            }
+                            Action::CycleFit => {
+                                view.cycle_fit();
+                                redraw_requested = true;
+                            }
+                            Action::RotateClockwise => {
+                                transform.rotate_clockwise();
+                                animation.map_frames(DynamicImage::rotate90);
+                                image_changed = true;
+                                refit = true;
+                            }
+                            Action::RotateCounterClockwise => {
+                                transform.rotate_counter_clockwise();
+                                animation.map_frames(DynamicImage::rotate270);
+                                image_changed = true;
+                                refit = true;
+                            }
+                            Action::FlipHorizontal => {
+                                transform.flip_horizontal();
+                                animation.map_frames(DynamicImage::fliph);
+                                image_changed = true;
+                                redraw_requested = true;
+                            }
+                            Action::FlipVertical => {
+                                transform.flip_vertical();
+                                animation.map_frames(DynamicImage::flipv);
+                                image_changed = true;
+                                redraw_requested = true;
+                            }
+                            Action::PanLeft => {
+                                view.pan_by([-pan_x, 0.0], image_size, &size);
+                                redraw_requested = true;
+                            }
+                            Action::PanRight => {
+                                view.pan_by([pan_x, 0.0], image_size, &size);
+                                redraw_requested = true;
+                            }
+                            Action::PanUp => {
+                                view.pan_by([0.0, -pan_y], image_size, &size);
+                                redraw_requested = true;
+                            }
+                            Action::PanDown => {
+                                view.pan_by([0.0, pan_y], image_size, &size);
+                                redraw_requested = true;
+                            }
+                            Action::Next | Action::Previous | Action::First | Action::Last => {
+                                let step = match action {
+                                    Action::Next => Step::Next,
+                                    Action::Previous => Step::Previous,
+                                    Action::First => Step::First,
+                                    _ => Step::Last,
+                                };
+                                playlist.step(step);
+                                loading_step = Some(step);
+                                loader.load(playlist.current());
+                            }
+                        }
+
+                        if refit {
+                            // Quarter turns swap the sides, so fit the window to them again. A
+                            // fullscreen window keeps the monitor size and the view refits to it
+                            view.reset();
+                            refit_window(
+                                &window,
+                                &mut screen_size,
+                                [animation.image().width(), animation.image().height()],
+                                &config
+                            );
+                            redraw_requested = true;
+                        }
+                    }
+                    winit::event::WindowEvent::ScaleFactorChanged { new_inner_size, .. }
+                        if window.fullscreen().is_none() => {
+                            // Dragged onto a monitor with another DPI, so fit it there instead of
+                            // keeping the same logical size
+                            *new_inner_size = refit_size(
+                                &window,
+                                None,
+                                &mut screen_size,
+                                [animation.image().width(), animation.image().height()],
+                                &config
+                            );
+                            view.reset();
+                            redraw_requested = true;
+                    }
+                    winit::event::WindowEvent::ModifiersChanged(state) => {
+                        modifiers = state;
+                    }
+                    winit::event::WindowEvent::MouseWheel { delta, .. } => {
+                        let steps: f32 = match delta {
+                            MouseScrollDelta::LineDelta(_, y) => y,
+                            MouseScrollDelta::PixelDelta(position) => (position.y as f32) / 50.0,
+                        };
+                        let image_size = [animation.image().width(), animation.image().height()];
+                        let size = window.inner_size();
+                        // Scrolling fits read like a document, holding ctrl zooms instead
+                        match view.fit {
+                            Fit::Width if !modifiers.ctrl() => view.pan_by(
+                                [0.0, -steps * (size.height as f32) * PAN_STEP],
+                                image_size,
+                                &size
+                            ),
+                            Fit::Height if !modifiers.ctrl() => view.pan_by(
+                                [-steps * (size.width as f32) * PAN_STEP, 0.0],
+                                image_size,
+                                &size
+                            ),
+                            _ => {
+                                view.zoom_by(ZOOM_STEP.powf(steps), Some(cursor), image_size, &size)
                             }
-                            _ => {}
                         }
+                        redraw_requested = true;
+                    }
+                    winit::event::WindowEvent::MouseInput {
+                        state,
+                        button: MouseButton::Left,
+                        ..
+                    } => {
+                        dragging = state == ElementState::Pressed;
+                    }
+                    winit::event::WindowEvent::CursorMoved { position, .. } => {
+                        if dragging {
+                            view.pan_by(
+                                [(cursor.x - position.x) as f32, (cursor.y - position.y) as f32],
+                                [animation.image().width(), animation.image().height()],
+                                &window.inner_size()
+                            );
+                            redraw_requested = true;
+                        }
+                        cursor = position;
+                        overlay_changed |= inspecting;
+                    }
                     _ => {}
                 }
+            winit::event::Event::RedrawRequested(_) => {
+                // The window was uncovered, and the software renderer keeps no copy of it
+                overlay_changed = true;
+            }
+            winit::event::Event::NewEvents(_) if animation.tick(Instant::now()) => {
+                image_changed = true;
+                redraw_requested = true;
+            }
+            winit::event::Event::UserEvent(RivEvent::Loaded { generation, result }) => {
+                if !loader.finish(generation) {
+                    return;
+                }
+
+                match result {
+                    Ok(loaded) => {
+                        animation = loaded;
+                        if message.as_ref().is_some_and(|(_, expires)| expires.is_none()) {
+                            // The image that could not be shown has been replaced
+                            message = None;
+                        }
+                        if !transform.is_identity() {
+                            animation.map_frames(|image| transform.apply(image));
+                        }
+                        if loading_step.is_some() {
+                            view.reset();
+                        } else {
+                            // Keep looking at the same spot when the file is reloaded
+                            view.clamp(
+                                [animation.image().width(), animation.image().height()],
+                                &window.inner_size()
+                            );
+                        }
+                        image_changed = true;
+                        redraw_requested = true;
+                        slideshow.restart(Instant::now());
+
+                        if let Some(watcher) = watcher.as_mut() {
+                            if let Err(err) = watcher.watch(playlist.current()) {
+                                eprintln!("{}: {}", err, playlist.current().display());
+                            }
+                        }
+
+                        if refit_on_load && window.fullscreen().is_none() {
+                            // The header may have been unreadable, and the window may have
+                            // landed on another monitor, so check the real sizes once
+                            refit_on_load = false;
+                            refit_window(
+                                &window,
+                                &mut screen_size,
+                                [animation.image().width(), animation.image().height()],
+                                &config
+                            );
+                        }
+                    }
+                    Err(err) => {
+                        let name = display_name(playlist.current()).into_owned();
+                        let expires = Some(Instant::now() + MESSAGE_DURATION);
+                        overlay_changed = true;
+                        let (text, expires) = match loading_step {
+                            // A file caught mid write, the watcher will report it again
+                            None => (format!("Unable to reload {}", name), expires),
+                            // Nothing left to move on to, so keep the window open with the reason
+                            Some(_) if playlist.len() == 1 => {
+                                (format!("Unable to open {}", name), None)
+                            }
+                            Some(step) => {
+                                playlist.remove_current(step);
+                                loader.load(playlist.current());
+                                (format!("Skipped {}", name), expires)
+                            }
+                        };
+                        let text = format!("{}: {}", text, err.describe());
+                        eprintln!("{}", text);
+                        message = Some((text, expires));
+                    }
+                }
+            }
+            winit::event::Event::UserEvent(RivEvent::FileChanged(paths))
+                if watcher.as_ref().is_some_and(|watcher| watcher.is_target(&paths)) => {
+                    // Writers often touch a file several times, so wait for them to finish
+                    reload_at = Some(Instant::now() + RELOAD_DELAY);
+            }
             winit::event::Event::MainEventsCleared => {
+                if reload_at.is_some_and(|reload_at| reload_at <= Instant::now()) {
+                    reload_at = None;
+                    loading_step = None;
+                    loader.load(playlist.current());
+                }
+
+                if message
+                    .as_ref()
+                    .and_then(|(_, expires)| *expires)
+                    .is_some_and(|expires| expires <= Instant::now())
+                {
+                    message = None;
+                    overlay_changed = true;
+                }
+
+                if snap_at.is_some_and(|snap_at| snap_at <= Instant::now()) {
+                    snap_at = None;
+                    let size = window.inner_size();
+                    let snapped = aspect_size(
+                        &size,
+                        [animation.image().width(), animation.image().height()]
+                    );
+                    // Rounding can leave a pixel either way, which isn't worth a resize
+                    let off = size.width.abs_diff(snapped.width) > 1 ||
+                        size.height.abs_diff(snapped.height) > 1;
+                    if off && window.fullscreen().is_none() && !window.is_maximized() {
+                        window.set_inner_size(snapped);
+                    }
+                }
+
+                if slideshow.is_due(Instant::now()) && playlist.len() > 1 {
+                    if playlist.is_last() && !config.loop_slideshow {
+                        slideshow.stop();
+                    } else {
+                        playlist.step(Step::Next);
+                        loading_step = Some(Step::Next);
+                        loader.load(playlist.current());
+                    }
+                }
+
                 if resize_requested && last_resize.elapsed() >= debounce_duration {
                     last_resize = Instant::now() - debounce_duration;
                     resize_requested = false;
-
-                    redraw_surface(&mut pixels, &window.inner_size(), &stream_image).unwrap();
+                    redraw_requested = true;
                     if cfg!(debug_assertions) { println!("redrawing surface") }
                 }
+
+                if redraw_requested || overlay_changed {
+                    if image_changed {
+                        image_changed = false;
+                        renderer.upload(animation.image());
+                    }
+                    let size = window.inner_size();
+                    let image_size = [animation.image().width(), animation.image().height()];
+
+                    let mut lines: Vec<String> = Vec::new();
+                    if show_overlay {
+                        let status = Status {
+                            path: playlist.current(),
+                            index: playlist.index(),
+                            count: playlist.len(),
+                            dimensions: image_size,
+                            scale: view.scale(image_size, &size),
+                            format: animation.format(),
+                            file_size: animation.file_size(),
+                        };
+                        lines.push(status.format(&config.overlay_format));
+                    }
+                    if inspecting {
+                        lines.push(
+                            Inspection::at(animation.image(), &view, cursor, &size)
+                                .map(|inspection| inspection.describe())
+                                .unwrap_or_else(|| "-".into())
+                        );
+                    }
+                    if let Some((text, _)) = &message {
+                        lines.push(text.clone());
+                    }
+
+                    let frame = Frame {
+                        image: animation.image(),
+                        view: &view,
+                        background: &backgrounds[background],
+                        filter,
+                    };
+                    let drawn = renderer.draw(&size, &frame, &lines.join("\n"), !redraw_requested);
+                    if let Err(err) = drawn {
+                        // Lost surfaces were already retried, so the GPU is gone for good
+                        eprintln!("Error: {}", err.describe());
+                        *control_flow = ControlFlow::ExitWithCode(err.exit_code());
+                        return;
+                    }
+                    redraw_requested = false;
+                    overlay_changed = false;
+                }
+
+                let new_title = window_title(
+                    &playlist,
+                    &animation,
+                    view.fit,
+                    filter,
+                    &slideshow,
+                    loader.is_loading()
+                );
+                if new_title != title {
+                    window.set_title(&new_title);
+                    title = new_title;
+                }
             }
             _ => {}
         }
     })
 }
 
-fn calc_scale_factor(max_size: &u32, current_size: &u32, up_scale: Option<bool>) -> f32 {
-    if max_size >= current_size && !up_scale.unwrap_or(false) {
-        return 1 as f32;
+fn window_title(
+    playlist: &Playlist,
+    animation: &Animation,
+    fit: Fit,
+    filter: Filter,
+    slideshow: &Slideshow,
+    loading: bool
+) -> String {
+    let mut title = format!("RIV - {}", display_name(playlist.current()));
+    if playlist.len() > 1 {
+        title += &format!(" ({}/{})", playlist.index() + 1, playlist.len());
+    }
+    if animation.is_animated() {
+        if animation.is_paused() {
+            title += " [paused]";
+        }
+        if animation.speed() != 1.0 {
+            title += &format!(" [x{}]", animation.speed());
+        }
+    }
+    if fit != Fit::Contain {
+        title += &format!(" [{}]", fit.name());
+    }
+    if filter != Filter::Auto {
+        title += &format!(" [{}]", filter.name());
+    }
+    if slideshow.is_running() {
+        title += " [slideshow]";
+    }
+    if loading {
+        title += " [loading]";
     }
-    (*current_size as f32) / (*max_size as f32)
+    title
 }
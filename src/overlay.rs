@@ -0,0 +1,383 @@
+use super::playlist::display_name;
+use super::view::View;
+use embedded_graphics::{
+    mono_font::{ ascii::FONT_9X18, MonoTextStyle },
+    pixelcolor::BinaryColor,
+    prelude::*,
+    text::{ Baseline, Text },
+};
+use image::{ DynamicImage, GenericImageView, ImageFormat, Rgba, RgbaImage };
+use pixels::{ wgpu, Pixels };
+use std::convert::Infallible;
+use std::num::NonZeroU32;
+use std::path::Path;
+use winit::dpi::{ PhysicalPosition, PhysicalSize };
+
+/// Status shown when no `--overlay-format` is given
+pub const DEFAULT_FORMAT: &str =
+    "{name}  {index}/{count}  {width}x{height}  {zoom}%  {format}  {size}";
+/// Space in pixels between the text and the edge of its strip
+const PADDING: u32 = 4;
+const TEXT_COLOR: Rgba<u8> = Rgba([0xff, 0xff, 0xff, 0xff]);
+const STRIP_COLOR: Rgba<u8> = Rgba([0x00, 0x00, 0x00, 0xa0]);
+
+/// Facts about the image on display that the overlay format can refer to
+#[derive(Debug)]
+pub struct Status<'a> {
+    pub path: &'a Path,
+    pub index: usize,
+    pub count: usize,
+    pub dimensions: [u32; 2],
+    /// Displayed pixels per source pixel
+    pub scale: f32,
+    pub format: Option<ImageFormat>,
+    pub file_size: Option<u64>,
+}
+
+impl Status<'_> {
+    /// Fills in each `{field}` of `format`, leaving unknown fields as written
+    pub fn format(&self, format: &str) -> String {
+        let mut text = String::new();
+        let mut rest = format;
+        while let Some(start) = rest.find('{') {
+            text += &rest[..start];
+            rest = &rest[start..];
+            let value = rest.find('}').and_then(|end| Some((self.field(&rest[1..end])?, end)));
+            match value {
+                Some((value, end)) => {
+                    text += &value;
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    text.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        text + rest
+    }
+
+    fn field(&self, name: &str) -> Option<String> {
+        Some(match name {
+            "name" => display_name(self.path).into_owned(),
+            "path" => self.path.display().to_string(),
+            "index" => (self.index + 1).to_string(),
+            "count" => self.count.to_string(),
+            "width" => self.dimensions[0].to_string(),
+            "height" => self.dimensions[1].to_string(),
+            "zoom" => format!("{:.0}", self.scale * 100.0),
+            "format" => self.format
+                .and_then(|format| format.extensions_str().first())
+                .map(|extension| extension.to_uppercase())
+                .unwrap_or_else(|| "?".into()),
+            "size" => self.file_size.map(format_bytes).unwrap_or_else(|| "?".into()),
+            _ => return None,
+        })
+    }
+}
+
+/// Position and colour of the source pixel under the cursor
+#[derive(Debug, Clone, Copy)]
+pub struct Inspection {
+    pub pixel: [u32; 2],
+    pub color: Rgba<u8>,
+}
+
+impl Inspection {
+    /// Looks up the pixel under `cursor`, none when it is over the background
+    pub fn at(
+        image: &DynamicImage,
+        view: &View,
+        cursor: PhysicalPosition<f64>,
+        size: &PhysicalSize<u32>
+    ) -> Option<Inspection> {
+        let pixel = view.source_pixel(cursor, [image.width(), image.height()], size)?;
+        Some(Inspection { pixel, color: image.get_pixel(pixel[0], pixel[1]) })
+    }
+
+    /// `#rrggbb`, with the alpha appended only when the pixel isn't opaque
+    pub fn hex(&self) -> String {
+        let [red, green, blue, alpha] = self.color.0;
+        if alpha == 0xff {
+            format!("#{:02x}{:02x}{:02x}", red, green, blue)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", red, green, blue, alpha)
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let [red, green, blue, alpha] = self.color.0;
+        format!(
+            "{}, {}  rgba({}, {}, {}, {})  {}",
+            self.pixel[0], self.pixel[1], red, green, blue, alpha, self.hex()
+        )
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Lets embedded-graphics draw text straight into an image
+struct Canvas<'a>(&'a mut RgbaImage);
+
+impl OriginDimensions for Canvas<'_> {
+    fn size(&self) -> Size {
+        Size::new(self.0.width(), self.0.height())
+    }
+}
+
+impl DrawTarget for Canvas<'_> {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>
+    {
+        for Pixel(point, color) in pixels {
+            let (x, y) = (point.x as u32, point.y as u32);
+            if color.is_on() && x < self.0.width() && y < self.0.height() {
+                self.0.put_pixel(x, y, TEXT_COLOR);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Rasterises `text` onto a translucent strip just large enough to hold it
+pub fn render_text(text: &str) -> RgbaImage {
+    let style = MonoTextStyle::new(&FONT_9X18, BinaryColor::On);
+    let origin = Point::new(PADDING as i32, PADDING as i32);
+    let text = Text::with_baseline(text, origin, style, Baseline::Top);
+    let bounds = text.bounding_box().size;
+
+    let mut strip = RgbaImage::from_pixel(
+        bounds.width + PADDING * 2,
+        bounds.height + PADDING * 2,
+        STRIP_COLOR
+    );
+    let Ok(_) = text.draw(&mut Canvas(&mut strip));
+    strip
+}
+
+/// Draws the status strip in the bottom left corner on top of the rendered image
+#[derive(Debug)]
+pub struct Overlay {
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    sampler: wgpu::Sampler,
+    text: String,
+    texture: Option<OverlayTexture>,
+}
+
+#[derive(Debug)]
+struct OverlayTexture {
+    size: wgpu::Extent3d,
+    bind_group: wgpu::BindGroup,
+}
+
+impl Overlay {
+    pub fn new(pixels: &Pixels) -> Overlay {
+        let device: &wgpu::Device = pixels.device();
+        let shader = wgpu::include_wgsl!("../shaders/overlay.wgsl");
+        let module = device.create_shader_module(&shader);
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("riv_overlay_sampler"),
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("riv_overlay_uniform_buffer"),
+            size: (4 * std::mem::size_of::<f32>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("riv_overlay_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("riv_overlay_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("riv_overlay_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[wgpu::ColorTargetState {
+                    format: pixels.render_texture_format(),
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                }],
+            }),
+            multiview: None,
+        });
+
+        Overlay {
+            bind_group_layout,
+            render_pipeline,
+            uniform_buffer,
+            sampler,
+            text: String::new(),
+            texture: None,
+        }
+    }
+
+    /// Uploads `text` when it changed and pins the strip to the bottom left of the window
+    pub fn update(&mut self, pixels: &Pixels, text: &str, size: &PhysicalSize<u32>) {
+        if text != self.text || self.texture.is_none() {
+            self.text = text.to_string();
+            self.upload(pixels, &render_text(text));
+        }
+
+        let texture = match &self.texture {
+            Some(texture) => texture,
+            None => return,
+        };
+        let window = [size.width.max(1) as f32, size.height.max(1) as f32];
+        let dest: [f32; 4] = [
+            -1.0,
+            (texture.size.height as f32) / window[1] * 2.0 - 1.0,
+            (texture.size.width as f32) / window[0] * 2.0 - 1.0,
+            -1.0,
+        ];
+        let bytes: Vec<u8> = dest.iter().flat_map(|value| value.to_ne_bytes()).collect();
+        pixels.queue().write_buffer(&self.uniform_buffer, 0, &bytes);
+    }
+
+    fn upload(&mut self, pixels: &Pixels, strip: &RgbaImage) {
+        let device: &wgpu::Device = pixels.device();
+        let size = wgpu::Extent3d {
+            width: strip.width(),
+            height: strip.height(),
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("riv_overlay_texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        });
+        pixels.queue().write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            strip.as_raw(),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(4 * size.width),
+                rows_per_image: NonZeroU32::new(size.height),
+            },
+            size
+        );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("riv_overlay_bind_group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.uniform_buffer.as_entire_binding(),
+                },
+            ],
+        });
+        self.texture = Some(OverlayTexture { size, bind_group });
+    }
+
+    /// Adds a render pass drawing the strip over what is already in `render_target`
+    pub fn draw(&self, encoder: &mut wgpu::CommandEncoder, render_target: &wgpu::TextureView) {
+        let texture = match &self.texture {
+            Some(texture) => texture,
+            None => return,
+        };
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("riv_overlay_render_pass"),
+            color_attachments: &[wgpu::RenderPassColorAttachment {
+                view: render_target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+        rpass.set_pipeline(&self.render_pipeline);
+        rpass.set_bind_group(0, &texture.bind_group, &[]);
+        rpass.draw(0..4, 0..1);
+    }
+}
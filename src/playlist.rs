@@ -0,0 +1,129 @@
+use super::errors::{ RviError, Result };
+use image::ImageFormat;
+use rand::seq::SliceRandom;
+use std::borrow::Cow;
+use std::path::{ Path, PathBuf };
+
+/// Argument that reads an image from standard input
+pub const STDIN: &str = "-";
+
+/// Direction to move through the playlist in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Next,
+    Previous,
+    First,
+    Last,
+}
+
+/// Ordered list of images built from the paths given on the command line
+#[derive(Debug)]
+pub struct Playlist {
+    paths: Vec<PathBuf>,
+    index: usize,
+}
+
+impl Playlist {
+    /// Expands directories into the images they contain and keeps files as given
+    pub fn from_args(args: &[String]) -> Result<Playlist> {
+        let mut paths: Vec<PathBuf> = Vec::new();
+
+        for arg in args {
+            let path = PathBuf::from(arg);
+            if is_stdin(&path) {
+                paths.push(path);
+            } else if path.is_dir() {
+                let mut entries: Vec<PathBuf> = std::fs::read_dir(&path)?
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|entry| entry.is_file() && is_decodable(entry))
+                    .collect();
+                entries.sort();
+                paths.append(&mut entries);
+            } else {
+                paths.push(path);
+            }
+        }
+
+        if paths.is_empty() {
+            return Err(RviError::NoImages);
+        }
+
+        Ok(Playlist { paths, index: 0 })
+    }
+
+    pub fn current(&self) -> &Path {
+        &self.paths[self.index]
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Puts the images in a random order and starts from the first of them
+    pub fn shuffle(&mut self) {
+        self.paths.shuffle(&mut rand::thread_rng());
+        self.index = 0;
+    }
+
+    /// Whether the cursor is on the final image
+    pub fn is_last(&self) -> bool {
+        self.index == self.paths.len() - 1
+    }
+
+    /// Moves the cursor, wrapping around at either end for next and previous
+    pub fn step(&mut self, step: Step) {
+        let last = self.paths.len() - 1;
+        self.index = match step {
+            Step::Next if self.index == last => 0,
+            Step::Next => self.index + 1,
+            Step::Previous if self.index == 0 => last,
+            Step::Previous => self.index - 1,
+            Step::First => 0,
+            Step::Last => last,
+        };
+    }
+
+    /// Drops the current entry after it failed to decode and moves on in the direction of `step`
+    ///
+    /// Returns false once nothing is left to show.
+    pub fn remove_current(&mut self, step: Step) -> bool {
+        self.paths.remove(self.index);
+        if self.paths.is_empty() {
+            return false;
+        }
+
+        // Once at an end keep walking inwards rather than jumping back out
+        match step {
+            Step::Next | Step::First => self.index %= self.paths.len(),
+            Step::Previous | Step::Last => self.step(Step::Previous),
+        }
+        true
+    }
+}
+
+fn is_decodable(path: &Path) -> bool {
+    ImageFormat::from_path(path)
+        .map(|format| format.can_read())
+        .unwrap_or(false)
+}
+
+/// Whether `path` is the `-` argument, meaning read the image from standard input
+pub fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == STDIN
+}
+
+/// File name to show for `path`, or `stdin` for the `-` argument
+pub fn display_name(path: &Path) -> Cow<'_, str> {
+    if is_stdin(path) {
+        return "stdin".into();
+    }
+    path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default()
+}
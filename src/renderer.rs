@@ -0,0 +1,180 @@
+use super::config::Config;
+use super::errors::{ Result, RviError };
+use super::gpu::ImageRenderer;
+use super::graphics::{ redraw_overlay, redraw_surface, Filter, Frame };
+use super::overlay::Overlay;
+use super::software::SoftwareRenderer;
+use clap::ValueEnum;
+use image::DynamicImage;
+use pixels::wgpu::RequestAdapterOptions;
+use pixels::{ Pixels, PixelsBuilder, SurfaceTexture };
+use winit::dpi::PhysicalSize;
+use winit::window::Window;
+
+/// Which way frames get onto the window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Backend {
+    /// The GPU, or software on X11 when no graphics adapter can be used
+    Auto,
+    /// Any wgpu adapter, including a software one, failing when there is none
+    Gpu,
+    /// Scale on the CPU and copy the result straight to the window, for X11 only
+    Software,
+}
+
+/// Draws frames into the window
+pub trait Renderer {
+    /// Follows the window to a new size
+    fn resize(&mut self, size: &PhysicalSize<u32>);
+
+    /// Takes the image that following frames are drawn from
+    fn upload(&mut self, image: &DynamicImage);
+
+    /// Whether drawing with `filter` is cheap enough to follow a window resize live
+    fn is_fast(&self, filter: Filter) -> bool;
+
+    /// Draws `frame` with the `overlay` text on top, unless it is empty
+    ///
+    /// With `overlay_only` nothing but the overlay changed since the last draw.
+    fn draw(
+        &mut self,
+        size: &PhysicalSize<u32>,
+        frame: &Frame,
+        overlay: &str,
+        overlay_only: bool
+    ) -> Result<()>;
+}
+
+/// Draws through wgpu, scaling on the GPU when the adapter can hold the image
+pub struct PixelsRenderer {
+    pixels: Pixels,
+    image: Option<ImageRenderer>,
+    overlay: Overlay,
+}
+
+impl PixelsRenderer {
+    pub fn new(
+        window: &Window,
+        size: PhysicalSize<u32>,
+        low_performance_mode: bool
+    ) -> Result<PixelsRenderer> {
+        let (pixels, software): (Pixels, bool) =
+            match build_pixels(window, size, low_performance_mode, false) {
+                Err(RviError::PixelsError(pixels::Error::AdapterNotFound)) => {
+                    eprintln!("No GPU adapter found, falling back to a software adapter");
+                    (build_pixels(window, size, low_performance_mode, true)?, true)
+                }
+                result => (result?, false),
+            };
+
+        // A software adapter gains nothing from the texture path, so keep scaling on the CPU
+        let image = if software { None } else { Some(ImageRenderer::new(&pixels)) };
+        let overlay = Overlay::new(&pixels);
+        Ok(PixelsRenderer { pixels, image, overlay })
+    }
+}
+
+impl Renderer for PixelsRenderer {
+    fn resize(&mut self, size: &PhysicalSize<u32>) {
+        self.pixels.resize_surface(size.width, size.height);
+    }
+
+    fn upload(&mut self, image: &DynamicImage) {
+        if let Some(renderer) = self.image.as_mut() {
+            renderer.upload(&self.pixels, image);
+        }
+    }
+
+    fn is_fast(&self, filter: Filter) -> bool {
+        self.image.as_ref().is_some_and(|image| image.can_draw(filter))
+    }
+
+    fn draw(
+        &mut self,
+        size: &PhysicalSize<u32>,
+        frame: &Frame,
+        overlay: &str,
+        overlay_only: bool
+    ) -> Result<()> {
+        if !overlay.is_empty() {
+            self.overlay.update(&self.pixels, overlay, size);
+        }
+        let overlay = Some(&self.overlay).filter(|_| !overlay.is_empty());
+        if overlay_only {
+            redraw_overlay(&mut self.pixels, self.image.as_ref(), overlay, size, frame)
+        } else {
+            redraw_surface(&mut self.pixels, self.image.as_ref(), overlay, size, frame)
+        }
+    }
+}
+
+/// Picks the renderer for `config.renderer`, falling back to software on `auto`
+pub fn create_renderer(
+    window: &Window,
+    size: PhysicalSize<u32>,
+    config: &Config
+) -> Result<Box<dyn Renderer>> {
+    if config.renderer == Backend::Software {
+        return Ok(Box::new(SoftwareRenderer::new(window)?));
+    }
+
+    if cfg!(debug_assertions) {
+        println!("Building initial pixels with low performance mode as:");
+        dbg!(config.low_performance_mode);
+        // Enumerate adapters
+        let instance = pixels::wgpu::Instance::new(pixels::wgpu::Backends::all());
+        for adapter in instance.enumerate_adapters(pixels::wgpu::Backends::all()) {
+            dbg!(adapter);
+        }
+    }
+    match PixelsRenderer::new(window, size, config.low_performance_mode) {
+        Ok(renderer) => Ok(Box::new(renderer)),
+        Err(
+            err @ RviError::PixelsError(
+                pixels::Error::AdapterNotFound | pixels::Error::DeviceNotFound(_)
+            )
+        ) if config.renderer == Backend::Auto => {
+            eprintln!("{}, falling back to software rendering", err.describe());
+            match SoftwareRenderer::new(window) {
+                Ok(renderer) => Ok(Box::new(renderer)),
+                // Without the fallback the missing adapter is what the user needs to fix
+                Err(software) => {
+                    eprintln!("{}", software.describe());
+                    Err(err)
+                }
+            }
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Builds the wgpu backed pixels instance, optionally on a software adapter
+fn build_pixels(
+    window: &Window,
+    size: PhysicalSize<u32>,
+    low_performance_mode: bool,
+    force_fallback_adapter: bool
+) -> Result<Pixels> {
+    let surface: SurfaceTexture<Window> = SurfaceTexture::new(size.width, size.height, window);
+
+    let pixels: Pixels = PixelsBuilder::new(size.width, size.height, surface)
+        .device_descriptor(pixels::wgpu::DeviceDescriptor {
+            features: pixels::wgpu::Features::empty(),
+            limits: pixels::wgpu::Limits::default(),
+            label: None,
+        })
+        .request_adapter_options(RequestAdapterOptions {
+            power_preference: if low_performance_mode {
+                pixels::wgpu::PowerPreference::default()
+            } else {
+                pixels::wgpu::PowerPreference::HighPerformance
+            },
+            compatible_surface: None,
+            force_fallback_adapter,
+        })
+        .wgpu_backend(pixels::wgpu::Backends::all())
+        .enable_vsync(false)
+        .build()?;
+
+    Ok(pixels)
+}
@@ -0,0 +1,67 @@
+use std::time::{ Duration, Instant };
+
+/// Interval used when the slideshow is started at runtime without `--slideshow`
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Timer that moves on to the next image at a fixed interval
+///
+/// The interval is counted from when an image finishes loading, so a slow decode never
+/// cuts the time an image stays on screen.
+#[derive(Debug)]
+pub struct Slideshow {
+    interval: Duration,
+    running: bool,
+    /// When to advance, unset while the next image is still loading
+    next: Option<Instant>,
+}
+
+impl Slideshow {
+    pub fn new(interval: Duration, running: bool) -> Slideshow {
+        Slideshow { interval, running, next: None }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// When the next image is due, if running
+    pub fn deadline(&self) -> Option<Instant> {
+        self.next.filter(|_| self.running)
+    }
+
+    /// Whether it is time to advance, after which the timer waits for `restart`
+    pub fn is_due(&mut self, now: Instant) -> bool {
+        if self.deadline().is_some_and(|next| next <= now) {
+            self.next = None;
+            return true;
+        }
+        false
+    }
+
+    /// Starts counting down the interval again, called once an image is on screen
+    pub fn restart(&mut self, now: Instant) {
+        self.next = Some(now + self.interval);
+    }
+
+    pub fn toggle(&mut self) {
+        self.running = !self.running;
+        if self.running {
+            self.restart(Instant::now());
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.running = false;
+    }
+}
+
+/// Parses a `--slideshow` interval in seconds, fractions allowed
+pub fn parse_interval(value: &str) -> Result<Duration, String> {
+    let seconds: f64 = value
+        .parse()
+        .map_err(|_| format!("`{}` is not a number of seconds", value))?;
+    if !(seconds > 0.0 && seconds.is_finite()) {
+        return Err(format!("`{}` is not a positive number of seconds", value));
+    }
+    Ok(Duration::from_secs_f64(seconds))
+}
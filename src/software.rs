@@ -0,0 +1,249 @@
+use super::errors::Result;
+use super::graphics::{ render_image, Filter, Frame };
+use super::overlay::render_text;
+use super::renderer::Renderer;
+use image::DynamicImage;
+use winit::dpi::PhysicalSize;
+use winit::window::Window;
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd"
+))]
+use x11::Surface;
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd"
+)))]
+use unsupported::Surface;
+
+/// Scales frames on the CPU and copies them into the window without a graphics adapter
+///
+/// Every frame crosses to the display server in full, which suits remote X sessions and
+/// machines without a GPU but is slower than scaling on the GPU.
+pub struct SoftwareRenderer {
+    surface: Surface,
+    /// Window sized frame without the overlay, kept for redraws that only change the overlay
+    base: Vec<u32>,
+    base_size: PhysicalSize<u32>,
+}
+
+impl SoftwareRenderer {
+    pub fn new(window: &Window) -> Result<SoftwareRenderer> {
+        if cfg!(debug_assertions) {
+            println!("Rendering in software");
+        }
+        Ok(SoftwareRenderer {
+            surface: Surface::new(window)?,
+            base: Vec::new(),
+            base_size: PhysicalSize::new(0, 0),
+        })
+    }
+}
+
+impl Renderer for SoftwareRenderer {
+    fn resize(&mut self, _size: &PhysicalSize<u32>) {}
+
+    /// Frames are drawn from the image they are given, so there is nothing to keep
+    fn upload(&mut self, _image: &DynamicImage) {}
+
+    fn is_fast(&self, _filter: Filter) -> bool {
+        false
+    }
+
+    fn draw(
+        &mut self,
+        size: &PhysicalSize<u32>,
+        frame: &Frame,
+        overlay: &str,
+        overlay_only: bool
+    ) -> Result<()> {
+        if size.width == 0 || size.height == 0 {
+            return Ok(());
+        }
+        if !overlay_only || self.base_size != *size {
+            self.base = compose(size, frame);
+            self.base_size = *size;
+        }
+
+        let mut buffer: Vec<u32> = self.base.clone();
+        if !overlay.is_empty() {
+            blend_overlay(&mut buffer, size, overlay);
+        }
+        self.surface.present(&mut buffer, size)
+    }
+}
+
+/// Packs a colour as `0x00rrggbb`, the layout of 24 bit X11 visuals
+fn pack(red: u8, green: u8, blue: u8) -> u32 {
+    (red as u32) << 16 | (green as u32) << 8 | blue as u32
+}
+
+/// Renders the frame as the CPU path shows it, packed for the window
+fn compose(size: &PhysicalSize<u32>, frame: &Frame) -> Vec<u32> {
+    render_image(size, frame)
+        .pixels()
+        .map(|pixel| pack(pixel[0], pixel[1], pixel[2]))
+        .collect()
+}
+
+/// Blends the overlay strip into the bottom left corner, where the GPU path draws it
+fn blend_overlay(buffer: &mut [u32], size: &PhysicalSize<u32>, text: &str) {
+    let strip = render_text(text);
+    let top = size.height.saturating_sub(strip.height());
+    for (x, y, pixel) in strip.enumerate_pixels() {
+        if x >= size.width || top + y >= size.height {
+            continue;
+        }
+        let index = ((top + y) * size.width + x) as usize;
+        let behind = buffer[index];
+        let alpha = pixel[3] as u32;
+        let channel = |shift: u32, value: u8| {
+            let blended = (value as u32) * alpha + ((behind >> shift) & 0xff) * (0xff - alpha);
+            ((blended + 0x7f) / 0xff) as u8
+        };
+        buffer[index] = pack(channel(16, pixel[0]), channel(8, pixel[1]), channel(0, pixel[2]));
+    }
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd"
+))]
+mod x11 {
+    use crate::errors::{ Result, RviError };
+    use std::os::raw::c_char;
+    use winit::dpi::PhysicalSize;
+    use winit::platform::unix::WindowExtUnix;
+    use winit::window::Window;
+    use x11_dl::xlib;
+
+    /// Window that frames are copied into with `XPutImage`
+    pub struct Surface {
+        xlib: xlib::Xlib,
+        display: *mut xlib::Display,
+        window: xlib::Window,
+        gc: xlib::GC,
+        visual: *mut xlib::Visual,
+        depth: i32,
+    }
+
+    impl Surface {
+        pub fn new(window: &Window) -> Result<Surface> {
+            let (display, window) = match (window.xlib_display(), window.xlib_window()) {
+                (Some(display), Some(window)) => (display as *mut xlib::Display, window),
+                _ => return Err(software_error("the window is not on an X11 display")),
+            };
+            let xlib = xlib::Xlib::open().map_err(software_error)?;
+
+            // Safety: winit keeps the display open for as long as the event loop runs
+            unsafe {
+                let mut attributes: xlib::XWindowAttributes = std::mem::zeroed();
+                if (xlib.XGetWindowAttributes)(display, window, &mut attributes) == 0 {
+                    return Err(software_error("unable to query the window"));
+                }
+                let visual = attributes.visual;
+                let masks = ((*visual).red_mask, (*visual).green_mask, (*visual).blue_mask);
+                if !matches!(attributes.depth, 24 | 32) || masks != (0xff0000, 0xff00, 0xff) {
+                    return Err(software_error(format!(
+                        "the {} bit visual isn't 8 bit RGB",
+                        attributes.depth
+                    )));
+                }
+
+                let gc = (xlib.XCreateGC)(display, window, 0, std::ptr::null_mut());
+                Ok(Surface { xlib, display, window, gc, visual, depth: attributes.depth })
+            }
+        }
+
+        /// Copies a window sized buffer of packed RGB pixels to the window
+        pub fn present(&mut self, buffer: &mut [u32], size: &PhysicalSize<u32>) -> Result<()> {
+            unsafe {
+                let image = (self.xlib.XCreateImage)(
+                    self.display,
+                    self.visual,
+                    self.depth as u32,
+                    xlib::ZPixmap,
+                    0,
+                    buffer.as_mut_ptr() as *mut c_char,
+                    size.width,
+                    size.height,
+                    32,
+                    0
+                );
+                if image.is_null() {
+                    return Err(software_error("unable to create an image"));
+                }
+                // Xlib swaps the bytes itself when the server's order differs
+                (*image).byte_order = if cfg!(target_endian = "little") {
+                    xlib::LSBFirst
+                } else {
+                    xlib::MSBFirst
+                };
+                (self.xlib.XPutImage)(
+                    self.display,
+                    self.window,
+                    self.gc,
+                    image,
+                    0,
+                    0,
+                    0,
+                    0,
+                    size.width,
+                    size.height
+                );
+                // The buffer isn't Xlib's to free
+                (*image).data = std::ptr::null_mut();
+                (self.xlib.XDestroyImage)(image);
+                (self.xlib.XFlush)(self.display);
+            }
+            Ok(())
+        }
+    }
+
+    impl Drop for Surface {
+        fn drop(&mut self) {
+            unsafe {
+                (self.xlib.XFreeGC)(self.display, self.gc);
+            }
+        }
+    }
+
+    fn software_error(err: impl ToString) -> RviError {
+        RviError::SoftwareError(err.to_string())
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd"
+)))]
+mod unsupported {
+    use crate::errors::{ Result, RviError };
+    use winit::dpi::PhysicalSize;
+    use winit::window::Window;
+
+    pub struct Surface;
+
+    impl Surface {
+        pub fn new(_window: &Window) -> Result<Surface> {
+            Err(RviError::SoftwareError("only X11 displays are supported".into()))
+        }
+
+        pub fn present(&mut self, _buffer: &mut [u32], _size: &PhysicalSize<u32>) -> Result<()> {
+            Ok(())
+        }
+    }
+}
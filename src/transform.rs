@@ -0,0 +1,97 @@
+use image::DynamicImage;
+use std::io::{ BufRead, Seek };
+
+/// Rotation and mirroring applied to every image as it is displayed
+///
+/// Stored as an optional horizontal flip followed by a number of clockwise quarter turns,
+/// which is enough to express all eight orientations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Transform {
+    quarter_turns: u8,
+    flipped: bool,
+}
+
+impl Transform {
+    /// Builds a transform from a clockwise rotation in degrees, which must be a multiple of 90
+    pub fn from_degrees(degrees: u32) -> Transform {
+        Transform { quarter_turns: ((degrees / 90) % 4) as u8, flipped: false }
+    }
+
+    /// Builds the transform that undoes an EXIF orientation tag so the image displays upright
+    pub fn from_exif(orientation: u32) -> Transform {
+        let (quarter_turns, flipped) = match orientation {
+            2 => (0, true),
+            3 => (2, false),
+            4 => (2, true),
+            5 => (3, true),
+            6 => (1, false),
+            7 => (1, true),
+            8 => (3, false),
+            _ => (0, false),
+        };
+        Transform { quarter_turns, flipped }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Transform::default()
+    }
+
+    /// Whether width and height swap places
+    pub fn is_transposed(&self) -> bool {
+        self.quarter_turns & 1 == 1
+    }
+
+    pub fn apply(&self, image: &DynamicImage) -> DynamicImage {
+        let image = if self.flipped { image.fliph() } else { image.clone() };
+        match self.quarter_turns {
+            1 => image.rotate90(),
+            2 => image.rotate180(),
+            3 => image.rotate270(),
+            _ => image,
+        }
+    }
+
+    pub fn rotate_clockwise(&mut self) {
+        self.quarter_turns = (self.quarter_turns + 1) % 4;
+    }
+
+    pub fn rotate_counter_clockwise(&mut self) {
+        self.quarter_turns = (self.quarter_turns + 3) % 4;
+    }
+
+    /// Mirrors the displayed image left to right
+    pub fn flip_horizontal(&mut self) {
+        // Flipping after a rotation is the same as flipping first and rotating the other way
+        self.flipped = !self.flipped;
+        self.quarter_turns = (4 - self.quarter_turns) % 4;
+    }
+
+    /// Mirrors the displayed image top to bottom
+    pub fn flip_vertical(&mut self) {
+        // A vertical flip is a horizontal flip followed by a half turn
+        self.flip_horizontal();
+        self.quarter_turns = (self.quarter_turns + 2) % 4;
+    }
+}
+
+/// Parses a `--rotate` value, accepting negative angles as counter-clockwise
+pub fn parse_rotation(value: &str) -> Result<u32, String> {
+    let degrees: i32 = value
+        .parse()
+        .map_err(|_| format!("`{}` is not a whole number of degrees", value))?;
+    if degrees % 90 != 0 {
+        return Err(format!("`{}` is not a multiple of 90", value));
+    }
+    Ok(degrees.rem_euclid(360) as u32)
+}
+
+/// Reads the EXIF orientation of an image, treating missing or unreadable metadata as upright
+pub fn read_orientation<R: BufRead + Seek>(reader: &mut R) -> Transform {
+    let orientation = exif::Reader::new()
+        .read_from_container(reader)
+        .ok()
+        .and_then(|exif| {
+            exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?.value.get_uint(0)
+        });
+    orientation.map(Transform::from_exif).unwrap_or_default()
+}
@@ -0,0 +1,285 @@
+use clap::ValueEnum;
+use winit::dpi::{ PhysicalPosition, PhysicalSize };
+
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 64.0;
+
+/// How the image is scaled to the window before any zoom
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Fit {
+    /// Show the whole image
+    Contain,
+    /// Fill the window, cropping whatever sticks out
+    Cover,
+    /// Fill the width and scroll vertically
+    Width,
+    /// Fill the height and scroll horizontally
+    Height,
+    /// One image pixel per screen pixel
+    Actual,
+    /// The largest whole multiple that shows the whole image, for pixel art
+    Integer,
+}
+
+impl Fit {
+    /// Fit to switch to when cycling at runtime
+    pub fn next(&self) -> Fit {
+        match self {
+            Fit::Contain => Fit::Cover,
+            Fit::Cover => Fit::Width,
+            Fit::Width => Fit::Height,
+            Fit::Height => Fit::Actual,
+            Fit::Actual => Fit::Integer,
+            Fit::Integer => Fit::Contain,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Fit::Contain => "contain",
+            Fit::Cover => "cover",
+            Fit::Width => "width",
+            Fit::Height => "height",
+            Fit::Actual => "actual",
+            Fit::Integer => "integer",
+        }
+    }
+
+    /// Displayed pixels per source pixel before zooming
+    ///
+    /// Images too large to show whole even once are shrunk to fit under `Integer`.
+    pub fn scale(&self, image_size: [u32; 2], window_size: &PhysicalSize<u32>) -> f32 {
+        let width = (window_size.width as f32) / (image_size[0] as f32);
+        let height = (window_size.height as f32) / (image_size[1] as f32);
+        match self {
+            Fit::Contain => width.min(height),
+            Fit::Cover => width.max(height),
+            Fit::Width => width,
+            Fit::Height => height,
+            Fit::Actual => 1.0,
+            Fit::Integer if width.min(height) >= 1.0 => width.min(height).floor(),
+            Fit::Integer => width.min(height),
+        }
+    }
+}
+
+/// Zoom and pan applied on top of fitting the image to the window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct View {
+    pub fit: Fit,
+    /// Multiplier on the scale that fits the image to the window
+    pub zoom: f32,
+    /// Offset of the view centre from the image centre in source pixels
+    ///
+    /// Always clamped to the image before use, so an infinite offset means as far as it goes.
+    pub pan: [f32; 2],
+}
+
+impl Default for View {
+    fn default() -> Self {
+        View { fit: Fit::Contain, zoom: 1.0, pan: [0.0, 0.0] }
+    }
+}
+
+impl View {
+    pub fn new(fit: Fit) -> View {
+        let mut view = View { fit, ..View::default() };
+        view.reset();
+        view
+    }
+
+    /// Undoes zoom and pan, starting scrolling fits from the top or left edge
+    pub fn reset(&mut self) {
+        self.zoom = 1.0;
+        self.pan = match self.fit {
+            Fit::Width => [0.0, f32::NEG_INFINITY],
+            Fit::Height => [f32::NEG_INFINITY, 0.0],
+            _ => [0.0, 0.0],
+        };
+    }
+
+    /// Switches to the next fit and starts from its reset view
+    pub fn cycle_fit(&mut self) {
+        self.fit = self.fit.next();
+        self.reset();
+    }
+
+    /// Displayed pixels per source pixel
+    pub fn scale(&self, image_size: [u32; 2], window_size: &PhysicalSize<u32>) -> f32 {
+        self.fit.scale(image_size, window_size) * self.zoom
+    }
+
+    /// Region of the source image visible in the window as `[x, y, width, height]`
+    pub fn source_rect(&self, image_size: [u32; 2], window_size: &PhysicalSize<u32>) -> [u32; 4] {
+        let visible = self.visible_size(image_size, window_size);
+        let center = self.center(image_size, window_size);
+
+        let mut rect = [0; 4];
+        for axis in 0..2 {
+            let start = (center[axis] - visible[axis] / 2.0).floor().max(0.0) as u32;
+            let end = ((center[axis] + visible[axis] / 2.0).ceil() as u32).min(image_size[axis]);
+            rect[axis] = start.min(image_size[axis].saturating_sub(1));
+            rect[axis + 2] = end.saturating_sub(rect[axis]).max(1);
+        }
+        rect
+    }
+
+    /// Size of the cropped source region once scaled for display
+    pub fn output_size(
+        &self,
+        image_size: [u32; 2],
+        window_size: &PhysicalSize<u32>
+    ) -> PhysicalSize<u32> {
+        let scale = self.scale(image_size, window_size);
+        let rect = self.source_rect(image_size, window_size);
+        PhysicalSize::new(
+            ((rect[2] as f32) * scale).round().clamp(1.0, window_size.width.max(1) as f32) as u32,
+            ((rect[3] as f32) * scale).round().clamp(1.0, window_size.height.max(1) as f32) as u32
+        )
+    }
+
+    /// Source pixel drawn at `position` in the window, or none over the background
+    pub fn source_pixel(
+        &self,
+        position: PhysicalPosition<f64>,
+        image_size: [u32; 2],
+        window_size: &PhysicalSize<u32>
+    ) -> Option<[u32; 2]> {
+        if image_size.contains(&0) {
+            return None;
+        }
+        // Undo exactly what is drawn, the visible region stretched over the centred output
+        let rect = self.source_rect(image_size, window_size);
+        let output = self.output_size(image_size, window_size);
+        let output = [output.width, output.height];
+        let window = [window_size.width, window_size.height];
+        let position = [position.x, position.y];
+
+        let mut pixel = [0; 2];
+        for axis in 0..2 {
+            let offset = position[axis] - (window[axis].saturating_sub(output[axis]) / 2) as f64;
+            if offset < 0.0 || offset >= output[axis] as f64 {
+                return None;
+            }
+            let source = offset * (rect[axis + 2] as f64) / (output[axis] as f64);
+            pixel[axis] = (rect[axis] + source as u32).min(image_size[axis] - 1);
+        }
+        Some(pixel)
+    }
+
+    /// Multiplies the zoom while keeping the source pixel under `anchor` in place
+    pub fn zoom_by(
+        &mut self,
+        factor: f32,
+        anchor: Option<PhysicalPosition<f64>>,
+        image_size: [u32; 2],
+        window_size: &PhysicalSize<u32>
+    ) {
+        let window = [window_size.width as f32, window_size.height as f32];
+        let anchor = anchor
+            .map(|anchor| [anchor.x as f32, anchor.y as f32])
+            .unwrap_or([window[0] / 2.0, window[1] / 2.0]);
+
+        let old_scale = self.scale(image_size, window_size);
+        let center = self.center(image_size, window_size);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let new_scale = self.scale(image_size, window_size);
+
+        for axis in 0..2 {
+            let offset = anchor[axis] - window[axis] / 2.0;
+            let source = center[axis] + offset / old_scale;
+            self.pan[axis] = source - offset / new_scale - (image_size[axis] as f32) / 2.0;
+        }
+        self.clamp(image_size, window_size);
+    }
+
+    /// Moves the view by a distance given in window pixels
+    pub fn pan_by(
+        &mut self,
+        delta: [f32; 2],
+        image_size: [u32; 2],
+        window_size: &PhysicalSize<u32>
+    ) {
+        let scale = self.scale(image_size, window_size);
+        // Bring an edge or infinite pan back onto the image before moving away from it
+        self.clamp(image_size, window_size);
+        self.pan[0] += delta[0] / scale;
+        self.pan[1] += delta[1] / scale;
+        self.clamp(image_size, window_size);
+    }
+
+    /// Pulls the pan back so the view never leaves the image
+    pub fn clamp(&mut self, image_size: [u32; 2], window_size: &PhysicalSize<u32>) {
+        let center = self.center(image_size, window_size);
+        self.pan = [
+            center[0] - (image_size[0] as f32) / 2.0,
+            center[1] - (image_size[1] as f32) / 2.0,
+        ];
+    }
+
+    fn visible_size(&self, image_size: [u32; 2], window_size: &PhysicalSize<u32>) -> [f32; 2] {
+        let scale = self.scale(image_size, window_size);
+        [
+            ((window_size.width as f32) / scale).min(image_size[0] as f32),
+            ((window_size.height as f32) / scale).min(image_size[1] as f32),
+        ]
+    }
+
+    fn center(&self, image_size: [u32; 2], window_size: &PhysicalSize<u32>) -> [f32; 2] {
+        let visible = self.visible_size(image_size, window_size);
+        let mut center = [0.0; 2];
+        for axis in 0..2 {
+            let half_image = (image_size[axis] as f32) / 2.0;
+            let limit = half_image - visible[axis] / 2.0;
+            center[axis] = half_image + self.pan[axis].clamp(-limit, limit);
+        }
+        center
+    }
+}
+
+/// Largest size within `window_size` that has the same aspect ratio as the image
+pub fn aspect_size(window_size: &PhysicalSize<u32>, image_size: [u32; 2]) -> PhysicalSize<u32> {
+    let scale = Fit::Contain.scale(image_size, window_size);
+    PhysicalSize::new(
+        ((image_size[0] as f32) * scale).round().max(1.0) as u32,
+        ((image_size[1] as f32) * scale).round().max(1.0) as u32
+    )
+}
+
+/// Size of window that shows the whole image within `screen_percent` of the screen
+pub fn fit_window_size(
+    screen_size: &PhysicalSize<u32>,
+    image_size: [u32; 2],
+    screen_percent: u32,
+    up_scale: bool
+) -> PhysicalSize<u32> {
+    let mut scale: [f32; 2] = [
+        calc_scale_factor(
+            &((screen_size.width * screen_percent) / 100),
+            &image_size[0],
+            Some(up_scale)
+        ),
+        calc_scale_factor(
+            &((screen_size.height * screen_percent) / 100),
+            &image_size[1],
+            Some(up_scale)
+        ),
+    ];
+
+    float_ord::sort(&mut scale);
+
+    let scale: f32 = scale[1];
+
+    PhysicalSize::new(
+        ((image_size[0] as f32) / scale).ceil() as u32,
+        ((image_size[1] as f32) / scale).ceil() as u32
+    )
+}
+
+/// Factor to divide `current_size` by to fit `max_size`, or 1 if it fits and isn't scaled up
+pub fn calc_scale_factor(max_size: &u32, current_size: &u32, up_scale: Option<bool>) -> f32 {
+    if max_size >= current_size && !up_scale.unwrap_or(false) {
+        return 1_f32;
+    }
+    (*current_size as f32) / (*max_size as f32)
+}
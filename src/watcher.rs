@@ -0,0 +1,62 @@
+use super::errors::Result;
+use super::events::RivEvent;
+use super::playlist::is_stdin;
+use notify::{ event::EventKind, RecommendedWatcher, RecursiveMode, Watcher };
+use std::path::{ Path, PathBuf };
+use winit::event_loop::EventLoopProxy;
+
+/// Reports changes to the open image as `RivEvent::FileChanged`
+///
+/// The parent directory is watched rather than the file itself, so that files replaced by
+/// renaming a new copy over them are still noticed.
+#[derive(Debug)]
+pub struct FileWatcher {
+    watcher: RecommendedWatcher,
+    directory: Option<PathBuf>,
+    target: Option<PathBuf>,
+}
+
+impl FileWatcher {
+    pub fn new(proxy: EventLoopProxy<RivEvent>) -> Result<FileWatcher> {
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            match event {
+                Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                    // The event loop is gone once the window closes, so nobody is listening
+                    let _ = proxy.send_event(RivEvent::FileChanged(event.paths));
+                }
+                Ok(_) => {}
+                Err(err) => eprintln!("Error watching image: {}", err),
+            }
+        })?;
+
+        Ok(FileWatcher { watcher, directory: None, target: None })
+    }
+
+    /// Switches to watching `path`, standard input has nothing to watch
+    pub fn watch(&mut self, path: &Path) -> Result<()> {
+        self.target = None;
+        if is_stdin(path) {
+            return Ok(());
+        }
+
+        let path: PathBuf = path.canonicalize()?;
+        let directory: PathBuf = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+        if self.directory.as_ref() != Some(&directory) {
+            if let Some(old) = self.directory.take() {
+                // The directory may have been removed, which already ends the watch
+                let _ = self.watcher.unwatch(&old);
+            }
+            self.watcher.watch(&directory, RecursiveMode::NonRecursive)?;
+            self.directory = Some(directory);
+        }
+
+        self.target = Some(path);
+        Ok(())
+    }
+
+    /// Whether any of `paths` from a change event is the watched image
+    pub fn is_target(&self, paths: &[PathBuf]) -> bool {
+        self.target.as_ref().is_some_and(|target| paths.contains(target))
+    }
+}
@@ -1,26 +1,205 @@
+use super::config::Config;
 use super::errors::{ RviError, Result };
+use super::events::RivEvent;
+use super::view::fit_window_size;
+use std::str::FromStr;
 use winit::{
     dpi::{ PhysicalSize, PhysicalPosition },
     event_loop::EventLoop,
     monitor::MonitorHandle,
-    window::{ Window, WindowBuilder },
+    window::{ Fullscreen, Window, WindowBuilder },
 };
 
-pub fn create_window(event_loop: &EventLoop<()>, size: PhysicalSize<u32>) -> Result<Window> {
-    WindowBuilder::new()
+/// Opens the window on `monitor`, placed on it as `--position` asks
+pub fn create_window(
+    event_loop: &EventLoop<RivEvent>,
+    size: PhysicalSize<u32>,
+    monitor: Option<&MonitorHandle>,
+    config: &Config
+) -> Result<Window> {
+    let mut builder = WindowBuilder::new()
         .with_title("RIV")
         .with_inner_size(size)
-        .with_position(PhysicalPosition::new(20, 20))
-        .build(&event_loop)
+        .with_fullscreen(config.fullscreen.then(|| Fullscreen::Borderless(monitor.cloned())))
+        .with_decorations(!config.borderless)
+        .with_always_on_top(config.always_on_top);
+    if let Some(monitor) = monitor {
+        builder = builder.with_position(place_on(monitor, config.position, size));
+    }
+    builder
+        .build(event_loop)
 		.map_err(RviError::WindowError)
 }
 
-pub fn get_screen_size(
-    event_loop: &EventLoop<()>
-) -> Result<PhysicalSize<u32>> {
-    let primary_monitor: MonitorHandle = event_loop
-        .primary_monitor()
-        .ok_or(RviError::NoPrimaryMonitor)?;
+/// Where `--position` puts the window on its monitor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    Center,
+    /// Offset of the window's top left corner from the monitor's
+    At(PhysicalPosition<i32>),
+}
+
+impl FromStr for Placement {
+    type Err = String;
+
+    /// Accepts `center` or an `X,Y` offset in pixels
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "center" | "centre" => Ok(Placement::Center),
+            _ => parse_position(value)
+                .map(Placement::At)
+                .map_err(|_| format!("`{}` is not center or a position of the form X,Y", value)),
+        }
+    }
+}
+
+/// Top left corner for a window of `size` placed on `monitor`
+pub fn place_on(
+    monitor: &MonitorHandle,
+    placement: Placement,
+    size: PhysicalSize<u32>
+) -> PhysicalPosition<i32> {
+    let origin = monitor.position();
+    let offset = match placement {
+        Placement::At(offset) => offset,
+        Placement::Center => {
+            let screen = monitor.size();
+            PhysicalPosition::new(
+                (screen.width.saturating_sub(size.width) / 2) as i32,
+                (screen.height.saturating_sub(size.height) / 2) as i32
+            )
+        }
+    };
+    PhysicalPosition::new(origin.x + offset.x, origin.y + offset.y)
+}
+
+/// Finds the `--monitor` by index or name, or picks the primary monitor without one
+///
+/// Returns none only when no monitor can be found at all, as on some Wayland compositors.
+pub fn find_monitor(
+    event_loop: &EventLoop<RivEvent>,
+    monitor: Option<&str>
+) -> Result<Option<MonitorHandle>> {
+    let mut monitors = event_loop.available_monitors();
+    let wanted = match monitor {
+        Some(wanted) => wanted,
+        None => return Ok(event_loop.primary_monitor().or_else(|| monitors.next())),
+    };
+
+    let found = match wanted.parse::<usize>() {
+        Ok(index) => monitors.nth(index),
+        Err(_) => monitors.find(|monitor| {
+            monitor.name().is_some_and(|name| name.eq_ignore_ascii_case(wanted))
+        }),
+    };
+    found.map(Some).ok_or_else(|| RviError::NoSuchMonitor {
+        monitor: wanted.to_string(),
+        available: event_loop
+            .available_monitors()
+            .enumerate()
+            .map(|(index, monitor)| {
+                format!("{} {}", index, monitor.name().unwrap_or_else(|| "unnamed".into()))
+            })
+            .collect(),
+    })
+}
+
+/// The monitor after the one the window is on, wrapping around, or none with only one
+pub fn next_monitor(window: &Window) -> Option<MonitorHandle> {
+    let monitors: Vec<MonitorHandle> = window.available_monitors().collect();
+    if monitors.len() < 2 {
+        return None;
+    }
+    let current = window.current_monitor();
+    let index = monitors
+        .iter()
+        .position(|monitor| Some(monitor) == current.as_ref())
+        .map_or(0, |index| (index + 1) % monitors.len());
+    Some(monitors[index].clone())
+}
+
+/// Puts the window on `monitor` at `size`, or makes it fullscreen there if it already is
+pub fn move_to_monitor(
+    window: &Window,
+    monitor: &MonitorHandle,
+    size: PhysicalSize<u32>,
+    placement: Placement
+) {
+    if window.fullscreen().is_some() {
+        window.set_fullscreen(Some(Fullscreen::Borderless(Some(monitor.clone()))));
+    } else {
+        window.set_outer_position(place_on(monitor, placement, size));
+        window.set_inner_size(size);
+    }
+}
+
+/// Parses a `--position` value of the form `X,Y`
+pub fn parse_position(value: &str) -> std::result::Result<PhysicalPosition<i32>, String> {
+    let coordinate = |part: Option<&str>| part.and_then(|part| part.trim().parse::<i32>().ok());
+    let mut parts = value.splitn(2, ',');
+    match (coordinate(parts.next()), coordinate(parts.next())) {
+        (Some(x), Some(y)) => Ok(PhysicalPosition::new(x, y)),
+        _ => Err(format!("`{}` is not a position of the form X,Y", value)),
+    }
+}
+
+/// Switches between windowed and borderless fullscreen on the monitor the window is on
+pub fn toggle_fullscreen(window: &Window) {
+    if window.fullscreen().is_some() {
+        window.set_fullscreen(None);
+    } else {
+        window.set_fullscreen(Some(Fullscreen::Borderless(window.current_monitor())));
+    }
+}
+
+/// Screen size assumed when no monitor can be found and `--screen-size` wasn't given
+pub const DEFAULT_SCREEN_SIZE: PhysicalSize<u32> = PhysicalSize::new(640, 480);
+
+/// Size of the screen the window is on now, unless overridden with `--screen-size`
+pub fn current_screen_size(
+    window: &Window,
+    screen_size: Option<PhysicalSize<u32>>
+) -> Option<PhysicalSize<u32>> {
+    screen_size.or_else(|| window.current_monitor().map(|monitor| monitor.size()))
+}
+
+/// Fits a window around `image_size` on `monitor`, or the one the window is on, keeping that
+/// screen's size in `screen_size` for later fits
+pub fn refit_size(
+    window: &Window,
+    monitor: Option<&MonitorHandle>,
+    screen_size: &mut PhysicalSize<u32>,
+    image_size: [u32; 2],
+    config: &Config
+) -> PhysicalSize<u32> {
+    *screen_size = match monitor {
+        Some(monitor) => config.screen_size.unwrap_or(monitor.size()),
+        None => current_screen_size(window, config.screen_size).unwrap_or(*screen_size),
+    };
+    fit_window_size(screen_size, image_size, config.screen_percent, config.up_scale)
+}
+
+/// Resizes the window around `image_size` on its screen, leaving a fullscreen window be
+pub fn refit_window(
+    window: &Window,
+    screen_size: &mut PhysicalSize<u32>,
+    image_size: [u32; 2],
+    config: &Config
+) {
+    let fitted = refit_size(window, None, screen_size, image_size, config);
+    if window.fullscreen().is_none() && fitted != window.inner_size() {
+        window.set_inner_size(fitted);
+    }
+}
 
-    Ok(primary_monitor.size())
+/// Parses a `--screen-size` value of the form `WIDTHxHEIGHT`
+pub fn parse_screen_size(value: &str) -> std::result::Result<PhysicalSize<u32>, String> {
+    let length = |part: Option<&str>| {
+        part.and_then(|part| part.trim().parse::<u32>().ok()).filter(|length| *length > 0)
+    };
+    let mut parts = value.splitn(2, ['x', 'X']);
+    match (length(parts.next()), length(parts.next())) {
+        (Some(width), Some(height)) => Ok(PhysicalSize::new(width, height)),
+        _ => Err(format!("`{}` is not a screen size of the form WIDTHxHEIGHT", value)),
+    }
 }
@@ -0,0 +1,15 @@
+use clap::Parser;
+use riv::config::Config;
+
+#[test]
+fn the_last_of_a_flag_and_its_no_form_wins() {
+    let config = Config::try_parse_from(["riv", "--watch", "--no-watch", "--no-loop", "--loop"])
+        .unwrap();
+    assert!(!config.watch);
+    assert!(config.no_watch);
+    assert!(config.loop_slideshow);
+    assert!(!config.no_loop);
+
+    let config = Config::try_parse_from(["riv", "--no-auto-orient", "--auto-orient"]).unwrap();
+    assert!(!config.no_auto_orient);
+}
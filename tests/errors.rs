@@ -0,0 +1,53 @@
+use riv::errors::RviError;
+use std::collections::HashSet;
+use std::io::{ Error, ErrorKind };
+
+/// The sample error after `previous`, walking one of every variant the tests can build
+///
+/// A new variant needs an arm here before this compiles, and joins the walk once the arm before
+/// it returns a sample of it.
+fn next_sample(previous: Option<&RviError>) -> Option<RviError> {
+    match previous {
+        None => Some(RviError::ImageError(image::ImageError::IoError(Error::from(
+            ErrorKind::Other
+        )))),
+        Some(RviError::ImageError(_)) => Some(RviError::IoError(Error::from(ErrorKind::NotFound))),
+        Some(RviError::IoError(_)) => Some(RviError::PixelsError(pixels::Error::AdapterNotFound)),
+        Some(RviError::PixelsError(_)) => Some(RviError::SoftwareError(String::new())),
+        Some(RviError::SoftwareError(_)) => {
+            Some(RviError::ConfigError { path: "config.toml".into(), message: String::new() })
+        }
+        Some(RviError::ConfigError { .. }) => {
+            Some(RviError::WatchError(notify::Error::generic("gone")))
+        }
+        Some(RviError::WatchError(_)) => Some(RviError::NoPrimaryMonitor),
+        Some(RviError::NoPrimaryMonitor) => {
+            Some(RviError::NoSuchMonitor { monitor: "2".into(), available: Vec::new() })
+        }
+        Some(RviError::NoSuchMonitor { .. }) => Some(RviError::NoImages),
+        Some(RviError::NoImages) => None,
+        // winit keeps `OsError`'s constructor to itself, so the walk never reaches this
+        Some(RviError::WindowError(_)) => None,
+    }
+}
+
+#[test]
+fn exit_codes_are_distinct() {
+    let mut errors: Vec<RviError> = Vec::new();
+    while let Some(err) = next_sample(errors.last()) {
+        errors.push(err);
+    }
+    // Every variant but the window error
+    assert_eq!(errors.len(), 9);
+
+    let codes: HashSet<i32> = errors.iter().map(RviError::exit_code).collect();
+    assert_eq!(codes.len(), errors.len());
+    assert!(codes.iter().all(|code| *code > 2));
+}
+
+#[test]
+fn describe_includes_the_cause() {
+    let err = RviError::IoError(Error::new(ErrorKind::NotFound, "gone"));
+    assert_eq!(err.describe(), "An error occurred while loading the image: gone");
+    assert_eq!(RviError::NoImages.describe(), "No images found to open");
+}
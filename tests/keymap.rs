@@ -0,0 +1,37 @@
+use riv::keymap::{ KeyBinding, Keymap };
+use std::collections::HashMap;
+
+#[test]
+fn printed_keymap_reads_back_as_a_config_file() {
+    let printed = Keymap::default().to_string();
+    assert!(printed.starts_with("[keys]\n"));
+
+    let mut file: HashMap<String, HashMap<String, String>> = toml::from_str(&printed).unwrap();
+    let keys = file.remove("keys").unwrap();
+    assert_eq!(keys.len(), printed.lines().count() - 1);
+
+    // Unbind everything, then bind it all again from the printed table
+    let mut keymap = Keymap::default();
+    let unbind = keys.keys().map(|binding| (binding.clone(), "none".to_string())).collect();
+    keymap.apply(unbind).unwrap();
+    assert_eq!(keymap.to_string(), "[keys]\n");
+
+    keymap.apply(keys).unwrap();
+    assert_eq!(keymap.to_string(), printed);
+}
+
+#[test]
+fn apply_rejects_unknown_actions() {
+    let keys = HashMap::from([("r".to_string(), "explode".to_string())]);
+    assert!(Keymap::default().apply(keys).is_err());
+}
+
+#[test]
+fn bindings_ending_in_plus_parse() {
+    for binding in ["+", "ctrl++", "numpad+", "ctrl+numpad+", "shift+c"] {
+        let parsed: KeyBinding = binding.parse().unwrap();
+        assert_eq!(parsed.to_string(), binding);
+    }
+    assert!("ctrl+".parse::<KeyBinding>().is_err());
+    assert!("numpad".parse::<KeyBinding>().is_err());
+}
@@ -0,0 +1,66 @@
+use riv::errors::RviError;
+use riv::playlist::{ display_name, Playlist, Step };
+use std::path::Path;
+
+fn playlist(names: &[&str]) -> Playlist {
+    let args: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+    Playlist::from_args(&args).unwrap()
+}
+
+#[test]
+fn empty_arguments_are_an_error() {
+    assert!(matches!(Playlist::from_args(&[]), Err(RviError::NoImages)));
+}
+
+#[test]
+fn step_wraps_around() {
+    let mut playlist = playlist(&["a.png", "b.png", "c.png"]);
+    assert_eq!(playlist.len(), 3);
+
+    playlist.step(Step::Previous);
+    assert_eq!(playlist.current(), Path::new("c.png"));
+    assert!(playlist.is_last());
+
+    playlist.step(Step::Next);
+    assert_eq!(playlist.index(), 0);
+
+    playlist.step(Step::Last);
+    playlist.step(Step::First);
+    assert_eq!(playlist.current(), Path::new("a.png"));
+}
+
+#[test]
+fn remove_current_keeps_moving_the_same_way() {
+    let mut playlist = playlist(&["a.png", "b.png", "c.png"]);
+
+    playlist.step(Step::Next);
+    assert!(playlist.remove_current(Step::Next));
+    assert_eq!(playlist.current(), Path::new("c.png"));
+
+    assert!(playlist.remove_current(Step::Previous));
+    assert_eq!(playlist.current(), Path::new("a.png"));
+
+    assert!(!playlist.remove_current(Step::Next));
+    assert!(playlist.is_empty());
+}
+
+#[test]
+fn directories_expand_to_sorted_images() {
+    let dir = std::env::temp_dir().join(format!("riv-playlist-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for name in ["b.png", "a.jpg", "notes.txt"] {
+        std::fs::write(dir.join(name), b"").unwrap();
+    }
+
+    let playlist = playlist(&[dir.to_str().unwrap()]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(playlist.len(), 2);
+    assert_eq!(display_name(playlist.current()), "a.jpg");
+}
+
+#[test]
+fn stdin_has_a_display_name() {
+    assert_eq!(display_name(Path::new("-")), "stdin");
+    assert_eq!(display_name(Path::new("dir/image.png")), "image.png");
+}
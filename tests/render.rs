@@ -0,0 +1,140 @@
+use image::{ DynamicImage, ImageFormat, Rgba, RgbaImage };
+use riv::graphics::{ render_image, Background, Filter, Frame };
+use riv::loader::load_animation;
+use riv::view::View;
+use std::io::Cursor;
+use winit::dpi::PhysicalSize;
+
+/// Four by four image, opaque red on the left half and fully transparent on the right
+fn half_transparent() -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(4, 4, |x, _| {
+        if x < 2 { Rgba([0xff, 0, 0, 0xff]) } else { Rgba([0, 0, 0, 0]) }
+    }))
+}
+
+#[test]
+fn load_animation_decodes_from_memory() {
+    let mut bytes = Vec::new();
+    half_transparent().write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png).unwrap();
+
+    let animation = load_animation(Cursor::new(&bytes), true).unwrap();
+
+    assert!(!animation.is_animated());
+    assert_eq!(animation.format(), Some(ImageFormat::Png));
+    assert_eq!(animation.file_size(), Some(bytes.len() as u64));
+    assert_eq!(animation.image(), &half_transparent());
+}
+
+#[test]
+fn load_animation_rejects_garbage() {
+    assert!(load_animation(Cursor::new(b"not an image"), false).is_err());
+}
+
+#[test]
+fn load_animation_rejects_a_gif_without_frames() {
+    // A 1x1 screen holding only a graphic control extension before the trailer
+    let gif: &[u8] = &[
+        b'G', b'I', b'F', b'8', b'9', b'a', 1, 0, 1, 0, 0, 0, 0,
+        0x21, 0xf9, 4, 0, 0, 0, 0, 0,
+        0x3b,
+    ];
+    assert!(load_animation(Cursor::new(gif), false).is_err());
+}
+
+#[test]
+fn load_animation_rejects_an_empty_image() {
+    assert!(load_animation(Cursor::new(b"P6\n0 0\n255\n"), false).is_err());
+}
+
+#[test]
+fn render_image_composites_over_the_background() {
+    let image = half_transparent();
+    let frame = Frame {
+        image: &image,
+        view: &View::default(),
+        background: &Background::Solid([0, 0xff, 0]),
+        filter: Filter::Nearest,
+    };
+
+    let rendered = render_image(&PhysicalSize::new(8, 8), &frame);
+
+    assert_eq!(rendered.dimensions(), (8, 8));
+    assert_eq!(rendered.get_pixel(0, 0).0, [0xff, 0, 0]);
+    assert_eq!(rendered.get_pixel(7, 7).0, [0, 0xff, 0]);
+}
+
+#[test]
+fn background_parses_names_and_hex() {
+    assert_eq!("checker".parse(), Ok(Background::Checkerboard));
+    assert_eq!("#FF8000".parse(), Ok(Background::Solid([0xff, 0x80, 0x00])));
+    assert!("#ff80".parse::<Background>().is_err());
+}
+
+#[test]
+fn render_image_letterboxes_in_the_background_colour() {
+    let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 2, Rgba([0xff, 0, 0, 0xff])));
+    let frame = Frame {
+        image: &image,
+        view: &View::default(),
+        background: &Background::Solid([0, 0, 0xff]),
+        filter: Filter::Nearest,
+    };
+
+    let rendered = render_image(&PhysicalSize::new(8, 8), &frame);
+
+    assert_eq!(rendered.dimensions(), (8, 8));
+    assert_eq!(rendered.get_pixel(0, 0).0, [0, 0, 0xff]);
+    assert_eq!(rendered.get_pixel(0, 2).0, [0xff, 0, 0]);
+    assert_eq!(rendered.get_pixel(7, 5).0, [0xff, 0, 0]);
+    assert_eq!(rendered.get_pixel(7, 6).0, [0, 0, 0xff]);
+}
+
+#[test]
+fn checkerboard_letterboxes_in_black() {
+    assert_eq!(Background::Checkerboard.letterbox(), [0, 0, 0]);
+    assert_eq!(Background::Solid([1, 2, 3]).letterbox(), [1, 2, 3]);
+}
+
+#[test]
+fn filters_resample_differently() {
+    // Noise with sharp edges tells the kernels apart when scaling down
+    let image = DynamicImage::ImageRgba8(RgbaImage::from_fn(16, 16, |x, y| {
+        let value = ((x * 7 + y * 13) * 37 % 256) as u8;
+        Rgba([value, 0xff - value, (x * 16) as u8, 0xff])
+    }));
+    let filters = [
+        Filter::Nearest,
+        Filter::Triangle,
+        Filter::CatmullRom,
+        Filter::Gaussian,
+        Filter::Lanczos3,
+    ];
+    let rendered: Vec<_> = filters
+        .iter()
+        .map(|filter| {
+            let frame = Frame {
+                image: &image,
+                view: &View::default(),
+                background: &Background::Checkerboard,
+                filter: *filter,
+            };
+            render_image(&PhysicalSize::new(7, 7), &frame).into_raw()
+        })
+        .collect();
+
+    for (index, pixels) in rendered.iter().enumerate() {
+        for other in &rendered[index + 1..] {
+            assert_ne!(pixels, other);
+        }
+    }
+}
+
+#[test]
+fn kernel_filters_are_left_to_the_cpu() {
+    assert!(Filter::Auto.on_gpu());
+    assert!(Filter::Nearest.on_gpu());
+    assert!(Filter::Triangle.on_gpu());
+    assert!(!Filter::CatmullRom.on_gpu());
+    assert!(!Filter::Gaussian.on_gpu());
+    assert!(!Filter::Lanczos3.on_gpu());
+}
@@ -0,0 +1,55 @@
+use image::{ DynamicImage, GenericImageView, Rgb, RgbImage };
+use riv::transform::{ parse_rotation, Transform };
+
+/// Two by one image with a red left pixel and a blue right one
+fn sample() -> DynamicImage {
+    let mut image = RgbImage::new(2, 1);
+    image.put_pixel(0, 0, Rgb([0xff, 0, 0]));
+    image.put_pixel(1, 0, Rgb([0, 0, 0xff]));
+    DynamicImage::ImageRgb8(image)
+}
+
+#[test]
+fn parse_rotation_normalises_degrees() {
+    assert_eq!(parse_rotation("90"), Ok(90));
+    assert_eq!(parse_rotation("-90"), Ok(270));
+    assert_eq!(parse_rotation("450"), Ok(90));
+    assert!(parse_rotation("45").is_err());
+    assert!(parse_rotation("right").is_err());
+}
+
+#[test]
+fn exif_orientation_turns_the_image_upright() {
+    assert!(Transform::from_exif(1).is_identity());
+    assert!(Transform::from_exif(0).is_identity());
+    assert_eq!(Transform::from_exif(6), Transform::from_degrees(90));
+    assert_eq!(Transform::from_exif(8), Transform::from_degrees(270));
+    assert!(Transform::from_exif(5).is_transposed());
+    assert!(!Transform::from_exif(4).is_transposed());
+}
+
+#[test]
+fn rotating_swaps_dimensions() {
+    let image = Transform::from_degrees(90).apply(&sample());
+    assert_eq!(image.dimensions(), (1, 2));
+    assert_eq!(image.get_pixel(0, 0).0, [0xff, 0, 0, 0xff]);
+}
+
+#[test]
+fn flips_compose_with_rotation() {
+    let mut transform = Transform::default();
+    transform.flip_horizontal();
+    assert_eq!(transform.apply(&sample()).get_pixel(0, 0).0, [0, 0, 0xff, 0xff]);
+
+    transform.flip_horizontal();
+    assert!(transform.is_identity());
+
+    // A vertical and a horizontal flip together are a half turn
+    transform.flip_vertical();
+    transform.flip_horizontal();
+    assert_eq!(transform, Transform::from_degrees(180));
+
+    transform.rotate_clockwise();
+    transform.rotate_counter_clockwise();
+    assert_eq!(transform, Transform::from_degrees(180));
+}
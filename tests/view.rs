@@ -0,0 +1,124 @@
+use riv::view::{ aspect_size, calc_scale_factor, fit_window_size, Fit, View };
+use winit::dpi::{ PhysicalPosition, PhysicalSize };
+
+#[test]
+fn scale_factor_keeps_small_images_unless_scaling_up() {
+    assert_eq!(calc_scale_factor(&800, &400, None), 1.0);
+    assert_eq!(calc_scale_factor(&800, &400, Some(true)), 0.5);
+    assert_eq!(calc_scale_factor(&800, &1600, Some(false)), 2.0);
+}
+
+#[test]
+fn fit_window_size_shrinks_by_the_tighter_axis() {
+    let screen = PhysicalSize::new(1000, 1000);
+    assert_eq!(fit_window_size(&screen, [2000, 1000], 100, false), PhysicalSize::new(1000, 500));
+    assert_eq!(fit_window_size(&screen, [2000, 1000], 50, false), PhysicalSize::new(500, 250));
+    assert_eq!(fit_window_size(&screen, [200, 100], 90, false), PhysicalSize::new(200, 100));
+    assert_eq!(fit_window_size(&screen, [200, 100], 90, true), PhysicalSize::new(900, 450));
+}
+
+#[test]
+fn default_view_shows_the_whole_image() {
+    let view = View::default();
+    let window = PhysicalSize::new(400, 400);
+    assert_eq!(view.scale([200, 100], &window), 2.0);
+    assert_eq!(view.source_rect([200, 100], &window), [0, 0, 200, 100]);
+    assert_eq!(view.output_size([200, 100], &window), PhysicalSize::new(400, 200));
+}
+
+#[test]
+fn zoom_keeps_the_anchor_in_place() {
+    let mut view = View::default();
+    let image = [100, 100];
+    let window = PhysicalSize::new(100, 100);
+    let anchor = PhysicalPosition::new(25.0, 25.0);
+
+    view.zoom_by(2.0, Some(anchor), image, &window);
+
+    // Source pixel 25 stays a quarter of the way in, so the centre moves to 37.5
+    assert_eq!(view.zoom, 2.0);
+    assert_eq!(view.pan, [-12.5, -12.5]);
+    assert_eq!(view.source_rect(image, &window), [12, 12, 51, 51]);
+}
+
+#[test]
+fn pan_stops_at_the_image_edge() {
+    let mut view = View { zoom: 2.0, ..View::default() };
+    let image = [100, 100];
+    let window = PhysicalSize::new(100, 100);
+
+    view.pan_by([1000.0, -1000.0], image, &window);
+
+    assert_eq!(view.pan, [25.0, -25.0]);
+    assert_eq!(view.source_rect(image, &window), [50, 0, 50, 50]);
+}
+
+#[test]
+fn source_pixel_ignores_the_background() {
+    let view = View::default();
+    let window = PhysicalSize::new(400, 400);
+    let image = [200, 100];
+
+    assert_eq!(view.source_pixel(PhysicalPosition::new(0.0, 100.0), image, &window), Some([0, 0]));
+    assert_eq!(
+        view.source_pixel(PhysicalPosition::new(399.0, 299.0), image, &window),
+        Some([199, 99])
+    );
+    assert_eq!(view.source_pixel(PhysicalPosition::new(200.0, 50.0), image, &window), None);
+    assert_eq!(view.source_pixel(PhysicalPosition::new(200.0, 300.0), image, &window), None);
+}
+
+#[test]
+fn empty_images_have_no_pixels() {
+    let view = View::default();
+    let window = PhysicalSize::new(400, 400);
+
+    assert_eq!(view.source_rect([0, 0], &window)[..2], [0, 0]);
+    assert_eq!(view.source_pixel(PhysicalPosition::new(200.0, 200.0), [0, 10], &window), None);
+}
+
+#[test]
+fn fits_scale_against_the_window() {
+    let image = [200, 100];
+    let window = PhysicalSize::new(300, 300);
+    let scale = |fit: Fit| fit.scale(image, &window);
+
+    assert_eq!(scale(Fit::Contain), 1.5);
+    assert_eq!(scale(Fit::Cover), 3.0);
+    assert_eq!(scale(Fit::Width), 1.5);
+    assert_eq!(scale(Fit::Height), 3.0);
+    assert_eq!(scale(Fit::Actual), 1.0);
+    assert_eq!(scale(Fit::Integer), 1.0);
+    assert_eq!(Fit::Integer.scale([10, 10], &window), 30.0);
+    assert_eq!(Fit::Integer.scale([600, 600], &window), 0.5);
+}
+
+#[test]
+fn cover_crops_to_the_window_aspect() {
+    let view = View::new(Fit::Cover);
+    let window = PhysicalSize::new(100, 100);
+
+    assert_eq!(view.source_rect([200, 100], &window), [50, 0, 100, 100]);
+    assert_eq!(view.output_size([200, 100], &window), window);
+}
+
+#[test]
+fn fit_width_starts_at_the_top_and_scrolls() {
+    let mut view = View::new(Fit::Width);
+    let image = [100, 400];
+    let window = PhysicalSize::new(100, 100);
+    assert_eq!(view.source_rect(image, &window), [0, 0, 100, 100]);
+
+    view.pan_by([0.0, 50.0], image, &window);
+    assert_eq!(view.source_rect(image, &window), [0, 50, 100, 100]);
+
+    view.cycle_fit();
+    assert_eq!(view.fit, Fit::Height);
+    assert_eq!(view.source_rect(image, &window), [0, 0, 100, 400]);
+}
+
+#[test]
+fn aspect_size_shrinks_the_longer_side() {
+    assert_eq!(aspect_size(&PhysicalSize::new(800, 800), [200, 100]), PhysicalSize::new(800, 400));
+    assert_eq!(aspect_size(&PhysicalSize::new(300, 900), [200, 100]), PhysicalSize::new(300, 150));
+}
@@ -0,0 +1,26 @@
+use riv::window::{ parse_position, parse_screen_size, Placement };
+use winit::dpi::{ PhysicalPosition, PhysicalSize };
+
+#[test]
+fn parse_screen_size_reads_width_by_height() {
+    assert_eq!(parse_screen_size("1920x1080"), Ok(PhysicalSize::new(1920, 1080)));
+    assert_eq!(parse_screen_size("800X600"), Ok(PhysicalSize::new(800, 600)));
+    assert!(parse_screen_size("0x600").is_err());
+    assert!(parse_screen_size("1920,1080").is_err());
+    assert!(parse_screen_size("1920x").is_err());
+}
+
+#[test]
+fn parse_position_allows_negative_coordinates() {
+    assert_eq!(parse_position("20,20"), Ok(PhysicalPosition::new(20, 20)));
+    assert_eq!(parse_position("-1920, 0"), Ok(PhysicalPosition::new(-1920, 0)));
+    assert!(parse_position("20").is_err());
+}
+
+#[test]
+fn placement_is_center_or_an_offset() {
+    assert_eq!("center".parse(), Ok(Placement::Center));
+    assert_eq!("Centre".parse(), Ok(Placement::Center));
+    assert_eq!("0,-20".parse(), Ok(Placement::At(PhysicalPosition::new(0, -20))));
+    assert!("middle".parse::<Placement>().is_err());
+}